@@ -3,15 +3,19 @@ use std::collections::HashSet;
 use std::io::{BufRead, IsTerminal, Read};
 use std::path::PathBuf;
 use std::str::FromStr;
-use tldextract_rs::Source;
+use tldextract_rs::{Source, UnknownTld, Validation};
 
 #[derive(Clone, FromArgs)]
 /// TldExtract-rs
 pub struct Config {
-  /// specific sources(local file path or remote url) to prefix list,(eg. snapshot,remote)
+  /// specific sources(local file path or remote url) to prefix list,(eg. snapshot,remote,cache), or a fallback chain separated by "," (eg. remote,cache,snapshot)
   #[argh(option, short = 's')]
   pub source_uri: Option<String>,
 
+  /// compiled suffix tree to load, it is built and written there first, and rebuilt when the source or --disable-private-domains change (recorded in <path>.key)
+  #[argh(option)]
+  pub compiled: Option<PathBuf>,
+
   /// write output in json(lines) format
   #[argh(switch, short = 'j')]
   pub json: bool,
@@ -24,10 +28,34 @@ pub struct Config {
   #[argh(switch)]
   pub disable_private_domains: bool,
 
-  /// display filter result by field only (eg. -f suffix,domain,subdomain,registered_domain)
+  /// how to split hosts under a TLD that is not in the list (strict, default, error)
+  #[argh(option, default = "UnknownTld::Strict")]
+  pub unknown_tld: UnknownTld,
+
+  /// how strictly host names are checked (lenient, rfc1035, idna2008)
+  #[argh(option, default = "Validation::Lenient")]
+  pub validation: Validation,
+
+  /// allow "_" in subdomain labels, like "_dmarc" or "_sip._tcp"
+  #[argh(switch)]
+  pub allow_underscore: bool,
+
+  /// reject hosts with a trailing dot (FQDN) or a leading dot instead of stripping it
+  #[argh(switch)]
+  pub reject_dots: bool,
+
+  /// include the ASCII, Unicode and input spelling of each part in the json output
+  #[argh(switch)]
+  pub forms: bool,
+
+  /// display filter result by field only (eg. -f suffix,domain,subdomain,registered_domain,ip,is_private,is_default_rule,fqdn,leading_dot)
   #[argh(option, short = 'f')]
   pub filter: Option<String>,
 
+  /// explain which rules matched and why the split landed where it did
+  #[argh(switch)]
+  pub explain: bool,
+
   /// file to write output
   #[argh(option, short = 'o')]
   pub output: Option<PathBuf>,
@@ -43,6 +71,15 @@ impl From<Config> for Source {
 }
 
 impl Config {
+  /// What a compiled suffix tree is built from, see [Config::compiled]
+  pub fn compiled_key(&self) -> String {
+    format!(
+      "tldextract-cli {}\nsource: {}\ndisable private domains: {}\n",
+      env!("CARGO_PKG_VERSION"),
+      self.source_uri.as_deref().unwrap_or("snapshot"),
+      self.disable_private_domains
+    )
+  }
   pub fn targets(&self) -> Result<HashSet<String>, std::io::Error> {
     match &self.list {
       None => read_from_stdio(),
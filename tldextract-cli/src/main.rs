@@ -1,44 +1,84 @@
 use crossterm::style::Stylize;
 use std::fs::File;
+use std::path::{Path, PathBuf};
 use tldextract_cli::Config;
-use tldextract_rs::TLDExtract;
+use tldextract_rs::{Explain, MatchedRule, TLDExtract};
 
 fn main() -> Result<(), tldextract_rs::TLDExtractError> {
   let config: Config = argh::from_env();
-  let source = config.clone().into();
-  let suffix = tldextract_rs::SuffixList::new(source, config.disable_private_domains, None);
-  let mut extract = TLDExtract::new(suffix, true)?;
+  // 来源或者选项变了的话重新构建
+  let compiled = config
+    .compiled
+    .as_ref()
+    .map(|path| (path, config.compiled_key()));
+  let up_to_date = compiled
+    .as_ref()
+    .filter(|(path, key)| is_up_to_date(path, key))
+    .map(|(path, _)| path);
+  let extract = if let Some(path) = up_to_date {
+    // 跳过构建，直接加载编译好的树
+    TLDExtract::load_compiled(path, true)?
+  } else if config.source_uri.is_none() && !config.disable_private_domains {
+    // 默认的快照在编译时已经构建好了
+    TLDExtract::snapshot(true)
+  } else {
+    let source = config.clone().into();
+    let suffix = tldextract_rs::SuffixList::new(source, config.disable_private_domains, None);
+    TLDExtract::new(suffix, true)?
+  }
+  .unknown_tld(config.unknown_tld)
+  .validation(config.validation)
+  .allow_underscore(config.allow_underscore)
+  .reject_dots(config.reject_dots)
+  .forms(config.forms);
+  if let (None, Some((path, key))) = (up_to_date, &compiled) {
+    extract.save_compiled(path)?;
+    std::fs::write(key_path(path), key)?;
+  }
   let targets = config.targets()?;
-  let mut result = Vec::new();
-  for target in targets {
-    if let Ok(e) = extract.extract(&target) {
-      result.push(e.clone());
-      if config.json {
-        let s = serde_json::to_string(&e).unwrap();
-        println!("{s:}");
-      } else if let Some(f) = &config.filter {
-        let value = match f.to_lowercase().as_str() {
-          "subdomain" => e.subdomain,
-          "domain" => e.domain,
-          "suffix" => e.suffix,
-          "registered_domain" => e.registered_domain,
-          _ => None,
-        };
-        println!("{}", value.unwrap_or_default());
-      } else {
-        print!(
-          "[ {} |",
-          e.subdomain.unwrap_or("N/A".to_string()).dark_magenta()
-        );
-        print!(
-          " {}",
-          e.registered_domain.unwrap_or("N/A".to_string()).green()
-        );
-        print!(" | {} | ", e.domain.unwrap_or("N/A".to_string()).red());
-        print!("{}", e.suffix.unwrap_or_default().dark_blue());
-        println!(" ]");
+  if config.explain {
+    for target in targets {
+      match extract.explain(&target) {
+        Ok(e) if config.json => println!("{}", serde_json::to_string(&e).unwrap()),
+        Ok(e) => print_explain(&target, &e),
+        Err(err) => println!("{target}: {}", err.to_string().red()),
       }
     }
+    return Ok(());
+  }
+  let mut result = Vec::new();
+  for e in extract.extract_many(&targets).into_iter().flatten() {
+    result.push(e.clone());
+    if config.json {
+      let s = serde_json::to_string(&e).unwrap();
+      println!("{s:}");
+    } else if let Some(f) = &config.filter {
+      let value = match f.to_lowercase().as_str() {
+        "subdomain" => e.subdomain,
+        "domain" => e.domain,
+        "suffix" => e.suffix,
+        "registered_domain" => e.registered_domain,
+        "ip" => e.ip.map(|ip| ip.to_string()),
+        "is_private" => Some(e.is_private.to_string()),
+        "is_default_rule" => Some(e.is_default_rule.to_string()),
+        "fqdn" => Some(e.fqdn.to_string()),
+        "leading_dot" => Some(e.leading_dot.to_string()),
+        _ => None,
+      };
+      println!("{}", value.unwrap_or_default());
+    } else {
+      print!(
+        "[ {} |",
+        e.subdomain.unwrap_or("N/A".to_string()).dark_magenta()
+      );
+      print!(
+        " {}",
+        e.registered_domain.unwrap_or("N/A".to_string()).green()
+      );
+      print!(" | {} | ", e.domain.unwrap_or("N/A".to_string()).red());
+      print!("{}", e.suffix.unwrap_or_default().dark_blue());
+      println!(" ]");
+    }
   }
   if let Some(o) = config.output {
     let out = File::create(o).expect("Failed to create file");
@@ -46,3 +86,54 @@ fn main() -> Result<(), tldextract_rs::TLDExtractError> {
   }
   Ok(())
 }
+
+/// The file next to the compiled tree that records what it was built from
+fn key_path(path: &Path) -> PathBuf {
+  let mut key_path = path.as_os_str().to_owned();
+  key_path.push(".key");
+  PathBuf::from(key_path)
+}
+
+fn is_up_to_date(path: &Path, key: &str) -> bool {
+  path.exists() && std::fs::read_to_string(key_path(path)).is_ok_and(|k| k == key)
+}
+
+fn print_explain(target: &str, e: &Explain) {
+  println!("{}", target.bold());
+  println!("  host:    {}", e.host);
+  println!("  labels:  {}", e.labels.join(" | "));
+  println!("  source:  {}", e.source);
+  for node in e.visited.iter() {
+    println!(
+      "  visit:   {}{}",
+      "  ".repeat(node.depth - 1),
+      node.path.clone().dark_blue()
+    );
+  }
+  // 规则按列表中的写法显示，再加上来源
+  let about = |rule: &MatchedRule| match rule.origin {
+    Some(origin) => format!("{}, {origin}", rule.section),
+    None => rule.section.to_string(),
+  };
+  for rule in e.rules.iter() {
+    println!("  rule:    {} ({})", rule.text, about(rule));
+  }
+  if let Some(rule) = &e.prevailing {
+    println!("  wins:    {} ({})", rule.text.clone().green(), about(rule));
+  }
+  println!("  reason:  {}", e.reason);
+  let r = &e.result;
+  println!(
+    "  result:  [ {} | {} | {} | {} ]",
+    r.subdomain
+      .clone()
+      .unwrap_or("N/A".to_string())
+      .dark_magenta(),
+    r.registered_domain
+      .clone()
+      .unwrap_or("N/A".to_string())
+      .green(),
+    r.domain.clone().unwrap_or("N/A".to_string()).red(),
+    r.suffix.clone().unwrap_or_default().dark_blue()
+  );
+}
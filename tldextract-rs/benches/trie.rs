@@ -0,0 +1,153 @@
+//! Compare the flattened [TLDTrieTree] with the previous `HashMap` based trie, in lookup time
+//! and in the heap bytes each structure holds
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tldextract_rs::{Source, SuffixList, TLDTrieTree};
+
+/// The system allocator, counting the bytes that are in use
+struct Counting;
+
+static IN_USE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    IN_USE.fetch_add(layout.size(), Ordering::Relaxed);
+    unsafe { System.alloc(layout) }
+  }
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    IN_USE.fetch_sub(layout.size(), Ordering::Relaxed);
+    unsafe { System.dealloc(ptr, layout) }
+  }
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    IN_USE.fetch_add(new_size, Ordering::Relaxed);
+    IN_USE.fetch_sub(layout.size(), Ordering::Relaxed);
+    unsafe { System.realloc(ptr, layout, new_size) }
+  }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// The value made by `f` and the heap bytes it still holds, temporaries are not counted
+fn heap_size<T>(f: impl FnOnce() -> T) -> (T, usize) {
+  let before = IN_USE.load(Ordering::Relaxed);
+  let value = f();
+  (value, IN_USE.load(Ordering::Relaxed) - before)
+}
+
+/// The trie before it was flattened, every node owns a `HashMap<String, _>`
+#[derive(Debug, Default)]
+struct HashMapTrie {
+  node: HashMap<String, HashMapTrie>,
+  end: bool,
+}
+
+impl HashMapTrie {
+  fn insert(&mut self, keys: Vec<&str>) {
+    let keys_len = keys.len();
+    let mut current_node = &mut self.node;
+    for (index, mut key) in keys.clone().into_iter().enumerate() {
+      let mut is_exclude = false;
+      if index == keys_len - 1 && key.starts_with('!') {
+        key = &key[1..];
+        is_exclude = true;
+      }
+      let next_node = current_node.entry(key.to_string()).or_default();
+      if !is_exclude && (index == keys_len - 1)
+        || (key != "*" && index == keys_len - 2 && keys[index + 1] == "*")
+      {
+        next_node.end = true;
+      }
+      current_node = &mut next_node.node;
+    }
+  }
+  fn search(&self, host: &str) -> usize {
+    let mut suffix_len = 0;
+    let mut current_node = &self.node;
+    for (index, key) in host.rsplit('.').enumerate() {
+      let next_node = match current_node.get(key) {
+        Some(next_node) => next_node,
+        None => {
+          if let Some(next_node) = current_node.get("*") {
+            if next_node.end {
+              suffix_len = index + 1;
+            }
+          }
+          break;
+        }
+      };
+      if next_node.end {
+        suffix_len = index + 1;
+      }
+      current_node = &next_node.node;
+    }
+    suffix_len
+  }
+}
+
+fn hosts(suffix: &SuffixList) -> Vec<String> {
+  let mut hosts: Vec<String> = suffix
+    .public_suffixes
+    .iter()
+    .chain(suffix.private_suffixes.iter())
+    .map(|rule| format!("www.example.{}", rule.replace('*', "any").replace('!', "")))
+    .collect();
+  hosts.sort();
+  hosts.push("www.example.notatld".to_string());
+  hosts
+}
+
+fn bench_trie(c: &mut Criterion) {
+  let mut suffix = SuffixList::new(Source::Snapshot, false, None);
+  let trie = suffix.build().unwrap();
+  let (hash_map_trie, hash_map_bytes) = heap_size(|| {
+    let mut hash_map_trie = HashMapTrie::default();
+    for rule in suffix
+      .public_suffixes
+      .iter()
+      .chain(suffix.private_suffixes.iter())
+    {
+      hash_map_trie.insert(rule.rsplit('.').collect());
+    }
+    hash_map_trie
+  });
+  // 从二进制格式加载，得到的树拥有 nodes、hashes 和 labels 三块内存
+  let compiled = trie.to_compiled();
+  let (trie, flattened_bytes) = heap_size(|| TLDTrieTree::from_compiled(&compiled).unwrap());
+  println!(
+    "heap: flattened {flattened_bytes} bytes, hash_map {hash_map_bytes} bytes ({:.1}x)",
+    hash_map_bytes as f64 / flattened_bytes as f64
+  );
+  assert!(flattened_bytes < hash_map_bytes);
+  let hosts = hosts(&suffix);
+  // 两种结构的结果必须一致
+  for host in hosts.iter() {
+    assert_eq!(trie.search(host).len, hash_map_trie.search(host), "{host}");
+    assert_eq!(
+      trie.search(host),
+      TLDTrieTree::snapshot().search(host),
+      "{host}"
+    );
+  }
+  let mut group = c.benchmark_group("search");
+  group.bench_function("flattened", |b| {
+    b.iter(|| {
+      for host in hosts.iter() {
+        black_box(trie.search(black_box(host)));
+      }
+    })
+  });
+  group.bench_function("hash_map", |b| {
+    b.iter(|| {
+      for host in hosts.iter() {
+        black_box(hash_map_trie.search(black_box(host)));
+      }
+    })
+  });
+  group.finish();
+}
+
+criterion_group!(benches, bench_trie);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+//! Compile the embedded snapshot into a static suffix tree, so that
+//! `TLDTrieTree::snapshot()` does not need to parse the list at runtime
+use std::fmt::Write;
+use std::path::PathBuf;
+
+#[allow(dead_code)]
+#[path = "src/trie/builder.rs"]
+mod builder;
+
+#[allow(dead_code)]
+#[path = "src/uts46.rs"]
+mod uts46;
+
+include!("src/snapshot.rs");
+
+fn main() {
+  println!("cargo:rerun-if-changed=build.rs");
+  println!("cargo:rerun-if-changed=src/snapshot.rs");
+  println!("cargo:rerun-if-changed=src/trie/builder.rs");
+  println!("cargo:rerun-if-changed=src/uts46.rs");
+  // 和 SuffixList::process_line 用同样的解析和 IDNA 选项
+  let idna = uts46::IdnaOptions::new();
+  let mut parser = builder::ListParser::default();
+  let mut trie_tree = builder::TLDTrieBuilder::default();
+  for line in PUBLIC_SUFFIX_LIST.lines() {
+    if let Some((rule, private)) = parser.rule(line) {
+      let suffix = builder::rule_to_ascii(rule, |r| {
+        idna.domain_to_ascii(r).ok().map(|a| a.into_owned())
+      })
+      .unwrap_or_else(|| panic!("bad rule in the snapshot: {rule}"));
+      trie_tree.insert(&suffix, private);
+    }
+  }
+  let tree = trie_tree.flatten();
+  let mut code = String::new();
+  writeln!(
+    code,
+    "static SNAPSHOT_NODES: [TLDTrieNode; {}] = [",
+    tree.nodes.len()
+  )
+  .unwrap();
+  for n in tree.nodes.iter() {
+    writeln!(
+      code,
+      "node({}, {}, {}, {}, {}),",
+      n.label_start, n.label_end, n.children_start, n.children_end, n.flags
+    )
+    .unwrap();
+  }
+  writeln!(code, "];").unwrap();
+  writeln!(
+    code,
+    "static SNAPSHOT_HASHES: [u64; {}] = {:?};",
+    tree.hashes.len(),
+    tree.hashes
+  )
+  .unwrap();
+  writeln!(code, "static SNAPSHOT_LABELS: &str = {:?};", tree.labels).unwrap();
+  let out = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("snapshot_trie.rs");
+  std::fs::write(out, code).unwrap();
+}
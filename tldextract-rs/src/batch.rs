@@ -0,0 +1,189 @@
+use crate::error::Result;
+use crate::{ExtractResult, TLDExtract, TLDTrieTree};
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::io::{BufRead, ErrorKind};
+use std::sync::Arc;
+
+impl TLDExtract {
+  /// Extract many targets with one look at the trie, the results are in the order of the targets
+  /// ```rust
+  /// use tldextract_rs::TLDExtract;
+  /// let extract = TLDExtract::default();
+  /// let results = extract.extract_many(["www.example.com", "-bad.com", "foo.github.io"]);
+  /// assert_eq!(results[0].as_ref().unwrap().domain, Some("example".to_string()));
+  /// assert!(results[1].is_err());
+  /// assert_eq!(results[2].as_ref().unwrap().suffix, Some("github.io".to_string()));
+  /// ```
+  pub fn extract_many<I>(&self, targets: I) -> Vec<Result<ExtractResult>>
+  where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+  {
+    self.update_if_expired();
+    self.with_trie(|trie| {
+      targets
+        .into_iter()
+        .map(|target| self.extract_in(trie, target.as_ref()))
+        .collect()
+    })
+  }
+  /// Like [TLDExtract::extract_many], but on the rayon thread pool
+  /// ```rust
+  /// use tldextract_rs::TLDExtract;
+  /// let extract = TLDExtract::default();
+  /// let targets = vec!["www.example.com".to_string(); 1000];
+  /// let results = extract.par_extract(&targets);
+  /// assert_eq!(results.len(), 1000);
+  /// ```
+  #[cfg(feature = "rayon")]
+  pub fn par_extract<I>(&self, targets: I) -> Vec<Result<ExtractResult>>
+  where
+    I: IntoParallelIterator,
+    I::Item: AsRef<str>,
+  {
+    self.update_if_expired();
+    self.with_trie(|trie| {
+      targets
+        .into_par_iter()
+        .map(|target| self.extract_in(trie, target.as_ref()))
+        .collect()
+    })
+  }
+  /// Extract every line of a reader lazily with one look at the trie, see [ExtractLines]
+  /// ```rust
+  /// use tldextract_rs::TLDExtract;
+  /// let extract = TLDExtract::default();
+  /// let input = "www.example.com\n\nfoo.github.io\n".as_bytes();
+  /// let lines: Vec<String> = extract.extract_lines(input).map(|(line, _)| line).collect();
+  /// assert_eq!(lines, ["www.example.com", "foo.github.io"]);
+  /// ```
+  pub fn extract_lines<R: BufRead>(&self, reader: R) -> ExtractLines<'_, R> {
+    self.update_if_expired();
+    ExtractLines {
+      extract: self,
+      trie: self.trie(),
+      reader,
+      buf: Vec::new(),
+      done: false,
+    }
+  }
+}
+
+/// Iterator over the lines of a reader and their extract results, blank lines are skipped.
+///
+/// The trie is the one current when the iterator was made, an update in the meantime does not
+/// change it. A line that is not valid UTF-8 yields an empty line with the error, any other
+/// read error is yielded the same way and ends the iteration.
+#[derive(Debug)]
+pub struct ExtractLines<'e, R> {
+  extract: &'e TLDExtract,
+  // None 表示快照
+  trie: Option<Arc<TLDTrieTree>>,
+  reader: R,
+  // 每一行都读到同一个缓冲区里
+  buf: Vec<u8>,
+  done: bool,
+}
+
+impl<R: BufRead> Iterator for ExtractLines<'_, R> {
+  type Item = (String, Result<ExtractResult>);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
+    }
+    loop {
+      self.buf.clear();
+      match self.reader.read_until(b'\n', &mut self.buf) {
+        Ok(0) => return None,
+        Ok(_) => {}
+        Err(err) => {
+          self.done = true;
+          return Some((String::new(), Err(err.into())));
+        }
+      }
+      let line = match std::str::from_utf8(&self.buf) {
+        Ok(line) => line.trim_end_matches(['\n', '\r']),
+        // 非 UTF-8 的行已经读完了，可以继续读下一行
+        Err(err) => {
+          let err = std::io::Error::new(ErrorKind::InvalidData, err);
+          return Some((String::new(), Err(err.into())));
+        }
+      };
+      if line.trim().is_empty() {
+        continue;
+      }
+      let trie = self.trie.as_deref().unwrap_or(TLDTrieTree::snapshot());
+      let result = self.extract.extract_in(trie, line);
+      return Some((line.to_string(), result));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::{BufReader, Read};
+
+  /// Reads the bytes, then fails
+  struct Failing<'a>(&'a [u8]);
+
+  impl Read for Failing<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+      if self.0.is_empty() {
+        return Err(std::io::Error::other("broken pipe"));
+      }
+      self.0.read(buf)
+    }
+  }
+
+  fn lines(input: impl BufRead) -> Vec<(String, std::result::Result<String, String>)> {
+    let extract = TLDExtract::default();
+    extract
+      .extract_lines(input)
+      .map(|(line, result)| {
+        let result = result
+          .map(|r| r.registered_domain.unwrap_or_default())
+          .map_err(|e| e.to_string());
+        (line, result)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn blank_lines() {
+    let input = "\n  \r\nwww.example.com\r\n\t\nfoo.github.io";
+    let lines = lines(input.as_bytes());
+    assert_eq!(
+      lines,
+      [
+        ("www.example.com".to_string(), Ok("example.com".to_string())),
+        ("foo.github.io".to_string(), Ok("foo.github.io".to_string())),
+      ]
+    );
+  }
+
+  #[test]
+  fn invalid_utf8_continues() {
+    let input: &[u8] = b"a.example.com\n\xff\xfe.com\nb.example.com\n";
+    let lines = lines(input);
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0].1, Ok("example.com".to_string()));
+    assert_eq!(lines[1].0, "");
+    assert!(lines[1].1.is_err());
+    assert_eq!(
+      lines[2],
+      ("b.example.com".to_string(), Ok("example.com".to_string()))
+    );
+  }
+
+  #[test]
+  fn read_error_stops() {
+    let input = BufReader::new(Failing(b"a.example.com\nb.example.com\n"));
+    let lines = lines(input);
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1].0, "b.example.com");
+    assert_eq!(lines[2], (String::new(), Err("broken pipe".to_string())));
+  }
+}
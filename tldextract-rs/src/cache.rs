@@ -0,0 +1,198 @@
+use crate::error::Result;
+use crate::trie::fnv1a;
+use crate::TLDExtractError;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{IntoUrl, StatusCode};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// `$XDG_CACHE_HOME/tldextract-rs`, or `~/.cache/tldextract-rs`
+pub(crate) fn default_cache_dir() -> Option<PathBuf> {
+  let base = std::env::var_os("XDG_CACHE_HOME")
+    .map(PathBuf::from)
+    .filter(|p| p.is_absolute())
+    .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+  Some(base.join("tldextract-rs"))
+}
+
+/// The lines of a remote list and where they came from
+#[derive(Debug)]
+pub(crate) struct Fetched {
+  pub(crate) lines: Vec<String>,
+  /// Whether the lines are the cached copy, the server was not asked or could not be reached
+  pub(crate) from_cache: bool,
+  /// When the lines were last known to be current
+  pub(crate) updated: Option<SystemTime>,
+  /// A new download, written to the cache once its lines parsed into rules
+  pub(crate) unsaved: Option<Unsaved>,
+}
+
+/// A downloaded list that is not in the cache yet
+#[derive(Debug)]
+pub(crate) struct Unsaved {
+  cached: Cached,
+  cache_dir: PathBuf,
+  url: String,
+}
+
+impl Unsaved {
+  /// Replace the cached copy, an error writing the cache does not fail the build
+  pub(crate) fn save(self) {
+    let _ = self.cached.save(&self.cache_dir, &self.url);
+  }
+}
+
+/// A fetched list and the validators to ask the server whether it changed
+#[derive(Debug, Default)]
+struct Cached {
+  body: Vec<u8>,
+  etag: Option<String>,
+  last_modified: Option<String>,
+  // 元数据文件的修改时间，也就是最后一次下载或者确认没有变化的时间
+  validated: Option<SystemTime>,
+}
+
+impl Cached {
+  /// The files of an URL, named after the hash of the URL
+  fn paths(cache_dir: &Path, url: &str) -> (PathBuf, PathBuf) {
+    let hash = fnv1a(url.as_bytes());
+    (
+      cache_dir.join(format!("{hash:016x}.dat")),
+      cache_dir.join(format!("{hash:016x}.meta")),
+    )
+  }
+  fn load(cache_dir: &Path, url: &str) -> Option<Self> {
+    let (body_path, meta_path) = Cached::paths(cache_dir, url);
+    let mut cached = Cached {
+      body: std::fs::read(&body_path).ok()?,
+      validated: std::fs::metadata(&meta_path)
+        .or_else(|_| std::fs::metadata(&body_path))
+        .and_then(|m| m.modified())
+        .ok(),
+      ..Default::default()
+    };
+    // 元数据丢了也可以用，只是不能条件请求
+    for line in std::fs::read_to_string(meta_path)
+      .unwrap_or_default()
+      .lines()
+    {
+      match line.split_once(": ") {
+        Some(("etag", value)) => cached.etag = Some(value.to_string()),
+        Some(("last-modified", value)) => cached.last_modified = Some(value.to_string()),
+        _ => {}
+      }
+    }
+    Some(cached)
+  }
+  fn save(&self, cache_dir: &Path, url: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(cache_dir)?;
+    let (body_path, _) = Cached::paths(cache_dir, url);
+    // 先写临时文件再重命名，其他进程不会读到写了一半的列表
+    let tmp = body_path.with_extension("tmp");
+    std::fs::write(&tmp, &self.body)?;
+    std::fs::rename(&tmp, &body_path)?;
+    self.save_meta(cache_dir, url)
+  }
+  /// Write the validators, this also marks the cached copy as current
+  fn save_meta(&self, cache_dir: &Path, url: &str) -> std::io::Result<()> {
+    let (_, meta_path) = Cached::paths(cache_dir, url);
+    let mut meta = format!("url: {url}\n");
+    if let Some(etag) = &self.etag {
+      meta.push_str(&format!("etag: {etag}\n"));
+    }
+    if let Some(last_modified) = &self.last_modified {
+      meta.push_str(&format!("last-modified: {last_modified}\n"));
+    }
+    std::fs::write(meta_path, meta)
+  }
+  fn into_fetched(self, from_cache: bool, updated: Option<SystemTime>) -> Fetched {
+    Fetched {
+      lines: self.body.lines().map(|l| l.unwrap_or_default()).collect(),
+      from_cache,
+      updated,
+      unsaved: None,
+    }
+  }
+  /// The cached copy as it is, current as of the last download or revalidation
+  fn into_stale(self) -> Fetched {
+    let validated = self.validated;
+    self.into_fetched(true, validated)
+  }
+}
+
+/// The cached copy of the list, without asking the server
+pub(crate) fn cached<T: IntoUrl>(u: T, cache_dir: Option<&Path>) -> Result<Fetched> {
+  let url = u.into_url()?;
+  match cache_dir.and_then(|dir| Cached::load(dir, url.as_str())) {
+    Some(cached) => Ok(cached.into_stale()),
+    None => Err(TLDExtractError::Io(std::io::Error::new(
+      std::io::ErrorKind::NotFound,
+      format!("no cached copy of {url}"),
+    ))),
+  }
+}
+
+/// Fetch the list, revalidating the cached copy with `If-None-Match` / `If-Modified-Since`.
+///
+/// The cached copy is used when the server says it did not change, or when the fetch fails.
+/// A new download is not written to the cache here, see [Fetched::unsaved].
+pub(crate) fn fetch<T: IntoUrl>(u: T, cache_dir: Option<&Path>) -> Result<Fetched> {
+  let url = u.into_url()?;
+  let cached = cache_dir.and_then(|dir| Cached::load(dir, url.as_str()));
+  let mut request = reqwest::blocking::Client::new().get(url.clone());
+  if let Some(cached) = &cached {
+    if let Some(etag) = &cached.etag {
+      request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &cached.last_modified {
+      request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+  }
+  let response = match request.send().and_then(|r| r.error_for_status()) {
+    Ok(response) => response,
+    Err(err) => return cached.map(Cached::into_stale).ok_or(err.into()),
+  };
+  if response.status() == StatusCode::NOT_MODIFIED {
+    let cached = cached.ok_or(TLDExtractError::EmptyList)?;
+    if let Some(dir) = cache_dir {
+      let _ = cached.save_meta(dir, url.as_str());
+    }
+    return Ok(cached.into_fetched(false, Some(SystemTime::now())));
+  }
+  let header = |name| {
+    response
+      .headers()
+      .get(name)
+      .and_then(|v| v.to_str().ok())
+      .map(|v| v.to_string())
+  };
+  let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+  let body = match response.bytes() {
+    Ok(body) => body.to_vec(),
+    Err(err) => return cached.map(Cached::into_stale).ok_or(err.into()),
+  };
+  let fetched = Cached {
+    body,
+    etag,
+    last_modified,
+    validated: None,
+  };
+  let lines = fetched
+    .body
+    .lines()
+    .map(|l| l.unwrap_or_default())
+    .collect();
+  // 先不写缓存，空的或者解析失败的列表不能覆盖好的缓存
+  let unsaved = cache_dir.map(|dir| Unsaved {
+    cached: fetched,
+    cache_dir: dir.to_path_buf(),
+    url: url.to_string(),
+  });
+  Ok(Fetched {
+    lines,
+    from_cache: false,
+    updated: Some(SystemTime::now()),
+    unsaved,
+  })
+}
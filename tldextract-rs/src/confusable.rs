@@ -0,0 +1,160 @@
+use crate::{ExtractResult, IdnaOptions, TLDExtract};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use unicode_security::restriction_level::{RestrictionLevel, RestrictionLevelDetection};
+use unicode_security::{skeleton, GeneralSecurityProfile, MixedScript};
+
+/// How likely a label is meant to look like another one
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Risk {
+  /// ASCII, or one script (or a usual mix like Han and Hiragana) that does not look like ASCII
+  Low,
+  /// Latin mixed with one other recommended script, like Latin and Devanagari
+  Medium,
+  /// A risky script mix (like Latin and Cyrillic), a whole-script confusable of an ASCII
+  /// label, or characters outside the identifier profile
+  High,
+}
+
+/// The [UTS #39](https://www.unicode.org/reports/tr39/) analysis of one label,
+/// using the confusables data bundled with `unicode-security`
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfusableAnalysis {
+  /// The label in Unicode, like "реасе" in Cyrillic
+  pub label: String,
+  /// The UTS #39 skeleton of the label, two labels that look the same have the same skeleton,
+  /// like "peace"
+  pub skeleton: String,
+  /// Whether the label mixes scripts that do not usually go together
+  pub mixed_script: bool,
+  /// Whether the label is non-ASCII but its skeleton is ASCII, so it can pass for an ASCII label
+  pub whole_script_confusable: bool,
+  /// Characters outside the identifier profile of UTS #39
+  pub disallowed: Vec<char>,
+  /// The verdict
+  pub risk: Risk,
+}
+
+impl ConfusableAnalysis {
+  /// Analyse one label, in Unicode or in PunyCode, with the default [IdnaOptions]
+  /// ```rust
+  /// use tldextract_rs::{ConfusableAnalysis, Risk};
+  /// // Cyrillic "реасе"
+  /// let analysis = ConfusableAnalysis::new("xn--80ajb5bh");
+  /// assert_eq!(analysis.skeleton, "peace");
+  /// assert!(analysis.whole_script_confusable);
+  /// assert_eq!(analysis.risk, Risk::High);
+  /// assert_eq!(ConfusableAnalysis::new("apple").risk, Risk::Low);
+  /// ```
+  pub fn new(label: &str) -> Self {
+    ConfusableAnalysis::with_idna(label, &IdnaOptions::new())
+  }
+  /// Analyse one label, converted to Unicode with `idna`
+  /// ```rust
+  /// use tldextract_rs::{ConfusableAnalysis, IdnaOptions};
+  /// let transitional = IdnaOptions::new().transitional(true);
+  /// assert_eq!(ConfusableAnalysis::new("faß").label, "faß");
+  /// assert_eq!(ConfusableAnalysis::with_idna("faß", &transitional).label, "fass");
+  /// ```
+  pub fn with_idna(label: &str, idna: &IdnaOptions) -> Self {
+    let label = idna
+      .domain_to_unicode(label)
+      .unwrap_or_else(|| label.to_string());
+    let skeleton: String = skeleton(&label).collect();
+    let disallowed: Vec<char> = label
+      .chars()
+      .filter(|ch| !ch.identifier_allowed())
+      .collect();
+    let level = label.as_str().detect_restriction_level();
+    let mixed_script = !label.as_str().is_single_script();
+    let whole_script_confusable = !label.is_ascii() && skeleton.is_ascii();
+    let risk = if !disallowed.is_empty()
+      || whole_script_confusable
+      || level >= RestrictionLevel::MinimallyRestrictive
+    {
+      Risk::High
+    } else if level == RestrictionLevel::ModeratelyRestrictive {
+      Risk::Medium
+    } else {
+      Risk::Low
+    };
+    ConfusableAnalysis {
+      label,
+      skeleton,
+      mixed_script,
+      whole_script_confusable,
+      disallowed,
+      risk,
+    }
+  }
+}
+
+impl TLDExtract {
+  /// The confusable analysis of [ExtractResult::domain] with the [IdnaOptions] of the extractor,
+  /// `None` if there is no domain
+  /// ```rust
+  /// use tldextract_rs::{Risk, TLDExtract};
+  /// let extract = TLDExtract::default();
+  /// // Cyrillic "реасе"
+  /// let result = extract.extract("www.реасе.com").unwrap();
+  /// let analysis = extract.confusable_analysis(&result).unwrap();
+  /// assert_eq!(analysis.skeleton, "peace");
+  /// assert_eq!(analysis.risk, Risk::High);
+  /// ```
+  pub fn confusable_analysis(&self, result: &ExtractResult) -> Option<ConfusableAnalysis> {
+    let domain = result.domain.as_deref()?;
+    Some(ConfusableAnalysis::with_idna(domain, &self.idna))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn mixed_script() {
+    // 拉丁字母和西里尔字母混在一起，"pаypal" 的 "а" 是西里尔字母
+    let analysis = ConfusableAnalysis::new("p\u{430}ypal");
+    assert!(analysis.mixed_script);
+    assert_eq!(analysis.skeleton, "paypal");
+    assert_eq!(analysis.risk, Risk::High);
+    // 汉字和平假名是常见的组合
+    let analysis = ConfusableAnalysis::new("日本の");
+    assert!(!analysis.mixed_script);
+    assert_eq!(analysis.risk, Risk::Low);
+  }
+
+  #[test]
+  fn medium_risk() {
+    // 拉丁字母加上一种推荐的文字
+    let analysis = ConfusableAnalysis::new("abcनमस्ते");
+    assert!(analysis.mixed_script);
+    assert_eq!(analysis.risk, Risk::Medium);
+  }
+
+  #[test]
+  fn disallowed_chars() {
+    let analysis = ConfusableAnalysis::new("a☃b");
+    assert_eq!(analysis.disallowed, ['☃']);
+    assert_eq!(analysis.risk, Risk::High);
+    assert!(ConfusableAnalysis::new("apple").disallowed.is_empty());
+  }
+
+  #[test]
+  fn extractor_options() {
+    let extract = TLDExtract::snapshot(false).idna(IdnaOptions::new().transitional(true));
+    let result = extract.extract("www.faß.de").unwrap();
+    assert_eq!(result.domain.as_deref(), Some("fass"));
+    let analysis = extract.confusable_analysis(&result).unwrap();
+    assert_eq!(analysis.label, "fass");
+    // 用 Unicode 返回的时候也是按照提取器的选项转换
+    let extract = TLDExtract::snapshot(true).idna(IdnaOptions::new().transitional(true));
+    let result = extract.extract("www.faß.de").unwrap();
+    assert_eq!(
+      extract.confusable_analysis(&result).unwrap().label,
+      ConfusableAnalysis::with_idna("faß", &IdnaOptions::new().transitional(true)).label
+    );
+  }
+}
@@ -0,0 +1,235 @@
+use crate::error::Result;
+use crate::{ExtractRef, SuffixMatch, TLDExtractError, TLDTrieTree, UnknownTld};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A validated host name that knows where its public suffix starts, see [crate::TLDExtract::domain]
+///
+/// The name is kept in its normalized ASCII form, with the suffix tree of the extractor,
+/// so the parents look up their own suffix. Domains are equal when their names are,
+/// and are ordered label by label from the right (the canonical DNS order of RFC 4034),
+/// so subdomains sort right after their parent.
+/// ```rust
+/// use tldextract_rs::Domain;
+/// let domain: Domain = "a.b.example.co.uk".parse().unwrap();
+/// assert_eq!(domain.registered_domain(), Some("example.co.uk"));
+/// let parent = domain.parent().unwrap();
+/// assert_eq!(parent.to_string(), "b.example.co.uk");
+/// let names: Vec<String> = domain
+///   .ancestors_until_registrable()
+///   .map(|d| d.to_string())
+///   .collect();
+/// assert_eq!(names, ["a.b.example.co.uk", "b.example.co.uk", "example.co.uk"]);
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+#[derive(Clone)]
+pub struct Domain {
+  name: String,
+  // 后缀的标签数量，0 表示没有后缀
+  suffix_labels: usize,
+  is_private: bool,
+  lookup: Lookup,
+}
+
+/// How a domain finds the suffix of its parents, the same way the extractor did
+#[derive(Clone)]
+struct Lookup {
+  // None 表示快照
+  trie: Option<Arc<TLDTrieTree>>,
+  unknown_tld: UnknownTld,
+}
+
+impl Lookup {
+  fn search(&self, name: &str) -> SuffixMatch {
+    let trie = self.trie.as_deref().unwrap_or(TLDTrieTree::snapshot());
+    let suffix_match = trie.search(name);
+    // 父域名的顶级域名和原来一样，UnknownTld::Error 不会走到这里
+    if suffix_match.len == 0 && self.unknown_tld == UnknownTld::Default {
+      return suffix_match.default_rule();
+    }
+    suffix_match
+  }
+}
+
+impl Domain {
+  pub(crate) fn new(
+    extract_ref: &ExtractRef,
+    trie: Option<Arc<TLDTrieTree>>,
+    unknown_tld: UnknownTld,
+  ) -> Result<Self> {
+    if extract_ref.ip().is_some() {
+      return Err(TLDExtractError::NotDomain(extract_ref.host().to_string()));
+    }
+    Ok(Domain {
+      name: extract_ref.host().to_string(),
+      suffix_labels: extract_ref.suffix_match.len,
+      is_private: extract_ref.is_private(),
+      lookup: Lookup { trie, unknown_tld },
+    })
+  }
+  /// The normalized ASCII name, like "xn--mnchen-3ya.de"
+  pub fn as_str(&self) -> &str {
+    &self.name
+  }
+  /// The labels from left to right, "www.example.com" has "www", "example" and "com"
+  pub fn labels(&self) -> impl DoubleEndedIterator<Item = &str> {
+    self.name.split('.')
+  }
+  /// The labels in front of the registered domain, "a.b" of "a.b.example.co.uk"
+  pub fn subdomain_labels(&self) -> impl Iterator<Item = &str> {
+    // 没有后缀就没有注册域名，也没有子域名
+    let count = match self.suffix_labels {
+      0 => 0,
+      n => self.depth().saturating_sub(n + 1),
+    };
+    self.labels().take(count)
+  }
+  /// The number of labels, "a.b.example.co.uk" has 5
+  pub fn depth(&self) -> usize {
+    self.name.matches('.').count() + 1
+  }
+  /// The domain without its leftmost label, `None` for a single label.
+  ///
+  /// The suffix of the parent is looked up again: "a.ssl.fastly.net" is a public suffix,
+  /// but its parent "ssl.fastly.net" is a registrable domain under "net"
+  pub fn parent(&self) -> Option<Domain> {
+    let (_, name) = self.name.split_once('.')?;
+    let suffix_match = self.lookup.search(name);
+    Some(Domain {
+      name: name.to_string(),
+      suffix_labels: suffix_match.len,
+      is_private: suffix_match.is_private,
+      lookup: self.lookup.clone(),
+    })
+  }
+  /// This domain and its parents, up to and including the registered domain.
+  /// Nothing when there is no registered domain, like for a public suffix
+  pub fn ancestors_until_registrable(&self) -> impl Iterator<Item = Domain> {
+    let first = self.registered_domain().is_some().then(|| self.clone());
+    std::iter::successors(first, |domain| {
+      if domain.is_registrable() {
+        None
+      } else {
+        domain.parent()
+      }
+    })
+  }
+  /// Whether this is the registered domain itself, like "example.co.uk"
+  pub fn is_registrable(&self) -> bool {
+    self.suffix_labels > 0 && self.depth() == self.suffix_labels + 1
+  }
+  /// Whether this is a public suffix, like "co.uk" or "github.io"
+  pub fn is_public_suffix(&self) -> bool {
+    self.suffix_labels > 0 && self.depth() == self.suffix_labels
+  }
+  /// Whether the suffix matched a rule from the PRIVATE section, like "github.io"
+  pub fn is_private(&self) -> bool {
+    self.is_private
+  }
+  /// The "a.b" part of "a.b.example.co.uk"
+  pub fn subdomain(&self) -> Option<&str> {
+    match self.subdomain_labels().count() {
+      0 => None,
+      count => Some(&self.name[..self.label_start(count) - 1]),
+    }
+  }
+  /// The "co.uk" part of "a.b.example.co.uk"
+  pub fn suffix(&self) -> Option<&str> {
+    if self.suffix_labels == 0 {
+      return None;
+    }
+    let start = self.label_start(self.depth() - self.suffix_labels);
+    Some(&self.name[start..])
+  }
+  /// The "example.co.uk" part of "a.b.example.co.uk"
+  pub fn registered_domain(&self) -> Option<&str> {
+    if self.suffix_labels == 0 || self.depth() <= self.suffix_labels {
+      return None;
+    }
+    let start = self.label_start(self.depth() - self.suffix_labels - 1);
+    Some(&self.name[start..])
+  }
+  /// The byte offset of the label with `n` labels in front of it
+  fn label_start(&self, n: usize) -> usize {
+    match n.checked_sub(1) {
+      Some(n) => self
+        .name
+        .match_indices('.')
+        .nth(n)
+        .map_or(self.name.len(), |(index, _)| index + 1),
+      None => 0,
+    }
+  }
+}
+
+impl std::fmt::Debug for Domain {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    // 不打印整棵树
+    f.debug_struct("Domain")
+      .field("name", &self.name)
+      .field("suffix_labels", &self.suffix_labels)
+      .field("is_private", &self.is_private)
+      .finish_non_exhaustive()
+  }
+}
+
+impl Display for Domain {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.name)
+  }
+}
+
+impl FromStr for Domain {
+  type Err = TLDExtractError;
+
+  /// Parse with the process-wide extractor, see [crate::global]
+  fn from_str(s: &str) -> Result<Self> {
+    crate::global().domain(s)
+  }
+}
+
+impl TryFrom<String> for Domain {
+  type Error = TLDExtractError;
+
+  fn try_from(value: String) -> Result<Self> {
+    value.parse()
+  }
+}
+
+impl From<Domain> for String {
+  fn from(value: Domain) -> Self {
+    value.name
+  }
+}
+
+impl PartialEq for Domain {
+  fn eq(&self, other: &Self) -> bool {
+    self.name == other.name
+  }
+}
+
+impl Eq for Domain {}
+
+impl Hash for Domain {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.name.hash(state);
+  }
+}
+
+impl PartialOrd for Domain {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Domain {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.labels().rev().cmp(other.labels().rev())
+  }
+}
@@ -1,22 +1,262 @@
+use crate::IdnaOptions;
+use std::fmt::{Display, Formatter};
 use thiserror::Error;
 
 /// TLDExtractError
 pub type Result<T> = std::result::Result<T, TLDExtractError>;
 
 /// TLDExtractError Enum
+///
+/// Byte offsets of the domain errors point into the host after IDNA normalization,
+/// except when the IDNA processing itself rejects the host, then they point into the input.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum TLDExtractError {
-  /// invalid domain
-  #[error("invalid domain: '{0}'")]
-  DomainError(String),
-  /// suffix list error
-  #[error("suffix list error: '{0}'")]
-  SuffixListError(String),
-  /// Parse Error
+  /// a character that is not allowed in a host name
+  #[error("invalid character {ch:?} at byte {offset}")]
+  InvalidChar {
+    /// byte offset of the character
+    offset: usize,
+    /// the character
+    ch: char,
+  },
+  /// an empty label, like the middle of "a..b"
+  #[error("empty label at byte {offset}")]
+  EmptyLabel {
+    /// byte offset where the label should start
+    offset: usize,
+  },
+  /// a label longer than 63 bytes
+  #[error("label at byte {offset} is {len} bytes long, the limit is 63")]
+  LabelTooLong {
+    /// byte offset of the label
+    offset: usize,
+    /// length of the label
+    len: usize,
+  },
+  /// a name longer than 253 bytes
+  #[error("name is {len} bytes long, the limit is 253")]
+  NameTooLong {
+    /// length of the name
+    len: usize,
+  },
+  /// a hyphen where it is not allowed, like the start or the end of a label
+  #[error("hyphen at byte {offset}")]
+  Hyphen {
+    /// byte offset of the hyphen
+    offset: usize,
+  },
+  /// the IDNA (UTS #46) processing failed
+  #[error("idna error at byte {offset}: {kind}")]
+  Idna {
+    /// byte offset of the label or the character that failed, in the input host
+    offset: usize,
+    /// what went wrong
+    kind: IdnaErrorKind,
+  },
+  /// invalid IPv4 or IPv6 address
+  #[error("invalid ip address: '{0}'")]
+  InvalidIp(String),
+  /// an IP literal where a domain name is expected, see [crate::Domain]
+  #[error("not a domain name: '{0}'")]
+  NotDomain(String),
+  /// invalid port
+  #[error("invalid port: '{0}'")]
+  InvalidPort(String),
+  /// the TLD is not in the suffix list, see [crate::UnknownTld::Error]
+  #[error("unknown tld: '{0}'")]
+  UnknownTld(String),
+  /// an option value that cannot be parsed
+  #[error("invalid option: '{0}'")]
+  InvalidOption(String),
+  /// a line of the suffix list that is not a valid rule, see [crate::SuffixList::strict]
+  #[error("bad suffix list line {line}: '{text}'")]
+  BadListLine {
+    /// line number, starting from 1
+    line: usize,
+    /// the line
+    text: String,
+  },
+  /// a compiled trie that cannot be loaded, see [crate::TLDExtract::load_compiled]
+  #[error("bad compiled trie: {0}")]
+  BadCompiled(String),
+  /// the suffix list has no rules
+  #[error("suffix list is empty")]
+  EmptyList,
+  /// failed to fetch the suffix list
   #[cfg(feature = "reqwest")]
   #[error(transparent)]
-  ParseError(#[from] reqwest::Error),
+  Fetch(#[from] reqwest::Error),
   /// Io Error
   #[error(transparent)]
   Io(#[from] std::io::Error),
 }
+
+/// What the IDNA (UTS #46) processing rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IdnaErrorKind {
+  /// a character that UTS #46 disallows, or IDNA2008 with [crate::Validation::Idna2008]
+  DisallowedChar(char),
+  /// an "xn--" label that is not valid Punycode, or decodes to an invalid label
+  Punycode,
+  /// the label breaks another validity rule, like Bidi, CONTEXTJ or a leading combining mark
+  Validity,
+  /// a CONTEXTJ or CONTEXTO character where its context rule does not allow it,
+  /// see [crate::Validation::Idna2008]
+  Context(char),
+}
+
+impl Display for IdnaErrorKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      IdnaErrorKind::DisallowedChar(ch) => write!(f, "disallowed character {ch:?}"),
+      IdnaErrorKind::Punycode => write!(f, "invalid punycode"),
+      IdnaErrorKind::Validity => write!(f, "invalid label"),
+      IdnaErrorKind::Context(ch) => write!(f, "{ch:?} not allowed in this context"),
+    }
+  }
+}
+
+impl TLDExtractError {
+  /// Find out why the IDNA processing rejected the host, the `idna` crate does not tell
+  pub(crate) fn idna(host: &str, options: &IdnaOptions) -> Self {
+    // 长度单独检查，单个标签或字符的时候不用管
+    let per_label = options.verify_dns_length(false);
+    let is_valid = |s: &str| per_label.domain_to_ascii(s).is_ok();
+    let is_a_label = |label: &str| {
+      label
+        .get(..4)
+        .is_some_and(|p| p.eq_ignore_ascii_case("xn--"))
+    };
+    let mut offset = 0;
+    for label in host.split('.') {
+      if options.verify_dns_length {
+        // 最后的空标签是根域名，允许
+        if label.is_empty() && offset < host.len() {
+          return TLDExtractError::EmptyLabel { offset };
+        }
+        if let Ok(ascii) = per_label.domain_to_ascii(label) {
+          if ascii.len() > 63 {
+            return TLDExtractError::LabelTooLong {
+              offset,
+              len: ascii.len(),
+            };
+          }
+        }
+      }
+      if options.check_hyphens && !label.is_empty() {
+        if label.starts_with('-') {
+          return TLDExtractError::Hyphen { offset };
+        }
+        if label.ends_with('-') {
+          return TLDExtractError::Hyphen {
+            offset: offset + label.len() - 1,
+          };
+        }
+        if label.get(2..4) == Some("--") && !is_a_label(label) {
+          return TLDExtractError::Hyphen { offset: offset + 2 };
+        }
+      }
+      if !is_valid(label) {
+        let kind = if is_a_label(label) {
+          IdnaErrorKind::Punycode
+        } else {
+          // 单独一个组合字符也是无效的，所以前面加一个字母再试一次
+          let disallowed = label.char_indices().find(|(_, ch)| {
+            let mut buf = [0; 4];
+            !is_valid(ch.encode_utf8(&mut buf)) && !is_valid(&format!("a{ch}"))
+          });
+          match disallowed {
+            Some((index, ch)) => {
+              return TLDExtractError::Idna {
+                offset: offset + index,
+                kind: IdnaErrorKind::DisallowedChar(ch),
+              }
+            }
+            None => IdnaErrorKind::Validity,
+          }
+        };
+        return TLDExtractError::Idna { offset, kind };
+      }
+      offset += label.len() + 1;
+    }
+    if let Ok(ascii) = per_label.domain_to_ascii(host) {
+      let len = ascii.trim_end_matches('.').len();
+      if options.verify_dns_length && len > 253 {
+        return TLDExtractError::NameTooLong { len };
+      }
+    }
+    // 每个标签单独都是有效的，例如跨标签的 Bidi 规则
+    TLDExtractError::Idna {
+      offset: 0,
+      kind: IdnaErrorKind::Validity,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// What [TLDExtractError::idna] reports, as a string to compare
+  fn idna(host: &str, options: IdnaOptions) -> String {
+    // 分类器只在 IDNA 处理失败的时候调用
+    assert!(options.domain_to_ascii(host).is_err(), "{host}");
+    format!("{:?}", TLDExtractError::idna(host, &options))
+  }
+
+  #[test]
+  fn disallowed_char() {
+    let options = IdnaOptions::new();
+    assert_eq!(
+      idna("www.a\u{e000}b.com", options),
+      "Idna { offset: 5, kind: DisallowedChar('\\u{e000}') }"
+    );
+    // 偏移量是输入中的字节偏移
+    assert_eq!(
+      idna("例子.\u{fdd0}.com", options),
+      "Idna { offset: 7, kind: DisallowedChar('\\u{fdd0}') }"
+    );
+    let std3 = options.use_std3_ascii_rules(true);
+    assert_eq!(
+      idna("a.b_c.com", std3),
+      "Idna { offset: 3, kind: DisallowedChar('_') }"
+    );
+  }
+
+  #[test]
+  fn punycode_and_validity() {
+    let options = IdnaOptions::new();
+    assert_eq!(
+      idna("www.xn--a.com", options),
+      "Idna { offset: 4, kind: Punycode }"
+    );
+    // 组合字符不能在标签开头
+    assert_eq!(
+      idna("a.\u{301}b.com", options),
+      "Idna { offset: 2, kind: Validity }"
+    );
+  }
+
+  #[test]
+  fn hyphens() {
+    let options = IdnaOptions::new().check_hyphens(true);
+    assert_eq!(idna("-a.com", options), "Hyphen { offset: 0 }");
+    assert_eq!(idna("www.a-.com", options), "Hyphen { offset: 5 }");
+    assert_eq!(idna("www.ab--c.com", options), "Hyphen { offset: 6 }");
+  }
+
+  #[test]
+  fn lengths() {
+    let options = IdnaOptions::new().verify_dns_length(true);
+    assert_eq!(idna("a..com", options), "EmptyLabel { offset: 2 }");
+    let label = "a".repeat(64);
+    assert_eq!(
+      idna(&format!("www.{label}.com"), options),
+      "LabelTooLong { offset: 4, len: 64 }"
+    );
+    let name = ["a".repeat(63).as_str(); 4].join(".");
+    assert_eq!(idna(&name, options), "NameTooLong { len: 255 }");
+  }
+}
@@ -0,0 +1,177 @@
+use crate::{ExtractResult, SuffixMatch};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// The section of the suffix list a rule is from
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+  /// Domains delegated by ICANN, like "co.uk"
+  Icann,
+  /// Domains submitted by their owners, like "github.io"
+  Private,
+}
+
+impl Display for Section {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Section::Icann => write!(f, "ICANN"),
+      Section::Private => write!(f, "PRIVATE"),
+    }
+  }
+}
+
+/// Which source of the [crate::SuffixList] a rule was read from
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleOrigin {
+  /// [crate::SuffixList::source], or the snapshot
+  Main,
+  /// [crate::SuffixList::extra]
+  Extra,
+}
+
+impl Display for RuleOrigin {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      RuleOrigin::Main => write!(f, "main"),
+      RuleOrigin::Extra => write!(f, "extra"),
+    }
+  }
+}
+
+/// A rule as it is written in the suffix list, see [crate::SuffixList::listed_rules]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedRule {
+  /// The line of the list, like "*.个人.hk"
+  pub text: String,
+  /// The source the line was read from
+  pub origin: RuleOrigin,
+}
+
+/// A rule of the suffix list that matched the host
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedRule {
+  /// The rule in ASCII, like "*.kobe.jp", "!city.kobe.jp" or "xn--ciqpn.hk"
+  pub rule: String,
+  /// The rule as it is written in the list, like "个人.hk".
+  /// The same as [MatchedRule::rule] when the tree was loaded from a compiled file
+  pub text: String,
+  /// Which source the rule was read from, `None` when it is not known, like for a tree
+  /// loaded from a compiled file or a rule added to [crate::SuffixList::public_suffixes]
+  pub origin: Option<RuleOrigin>,
+  /// How many labels of the host the rule covers
+  pub labels: usize,
+  /// The section of the list the rule is from
+  pub section: Section,
+  /// Whether it is an exception rule, starting with "!"
+  pub is_exception: bool,
+}
+
+/// A node of the suffix tree visited while matching the host
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisitedNode {
+  /// Which label of the host matched the node, 1 is the rightmost label
+  pub depth: usize,
+  /// The label of the node, "*" for a wildcard
+  pub label: String,
+  /// The path from the root to the node, written like a rule
+  pub path: String,
+}
+
+/// Why the host was split where it was
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitReason {
+  /// The host is an IP address, it has no suffix
+  Ip,
+  /// An exception rule matched, the suffix is the rule without its leftmost label
+  Exception,
+  /// The rule with the most labels is the suffix
+  LongestRule,
+  /// The TLD is in the list but no rule matched, the implicit "*" rule made the TLD the suffix
+  ImplicitRule,
+  /// The TLD is not in the list, [crate::UnknownTld::Default] made the TLD the suffix
+  DefaultRule,
+  /// The TLD is not in the list, so there is no suffix
+  UnknownTld,
+}
+
+impl Display for SplitReason {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let reason = match self {
+      SplitReason::Ip => "the host is an IP address",
+      SplitReason::Exception => {
+        "an exception rule matched, the suffix is the rule without its leftmost label"
+      }
+      SplitReason::LongestRule => "the matching rule with the most labels is the suffix",
+      SplitReason::ImplicitRule => {
+        "no rule matched, the implicit \"*\" rule made the listed TLD the suffix"
+      }
+      SplitReason::DefaultRule => {
+        "the TLD is not in the list, the default \"*\" rule made it the suffix"
+      }
+      SplitReason::UnknownTld => "the TLD is not in the list, so there is no suffix",
+    };
+    write!(f, "{reason}")
+  }
+}
+
+/// What [crate::TLDExtract::explain] found out about a target
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone)]
+pub struct Explain {
+  /// The normalized host (lowercase ASCII)
+  pub host: String,
+  /// The labels of the normalized host from left to right
+  pub labels: Vec<String>,
+  /// Where the suffix list was read from, like "snapshot" or the path of a file
+  pub source: String,
+  /// Every node of the suffix tree visited, in the order of the walk
+  pub visited: Vec<VisitedNode>,
+  /// Every rule that matched the host
+  pub rules: Vec<MatchedRule>,
+  /// The rule that decided the suffix, if any
+  pub prevailing: Option<MatchedRule>,
+  /// Why the split landed where it did
+  pub reason: SplitReason,
+  /// The extract result
+  pub result: ExtractResult,
+}
+
+/// The nodes and rules recorded by [crate::TLDTrieTree] while searching a host
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Trace {
+  pub(crate) visited: Vec<VisitedNode>,
+  pub(crate) rules: Vec<MatchedRule>,
+  pub(crate) suffix_match: SuffixMatch,
+}
+
+impl Trace {
+  /// Fill in the text and the origin of the matched rules
+  pub(crate) fn annotate(&mut self, listed_rules: &HashMap<String, ListedRule>) {
+    for rule in self.rules.iter_mut() {
+      if let Some(listed) = listed_rules.get(&rule.rule) {
+        rule.text = listed.text.clone();
+        rule.origin = Some(listed.origin);
+      }
+    }
+  }
+  /// The rule that decided the suffix, the same way [crate::TLDTrieTree::search] picks it
+  pub(crate) fn prevailing(&self) -> Option<&MatchedRule> {
+    // 长度相同的时候 ICANN 优先
+    let longest = |is_exception: bool| {
+      self
+        .rules
+        .iter()
+        .filter(|r| r.is_exception == is_exception)
+        .max_by_key(|r| (r.labels, r.section == Section::Icann))
+    };
+    longest(true).or_else(|| longest(false))
+  }
+}
@@ -0,0 +1,179 @@
+use crate::{ExtractRef, IdnaOptions};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Characters that UTS #46 maps to "."
+const SEPARATORS: [char; 4] = ['.', '\u{3002}', '\u{FF0E}', '\u{FF61}'];
+
+/// One part of the host in each of its spellings
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DomainForms {
+  /// The PunyCode form, for DNS and storage keys, like "xn--mnchen-3ya.de"
+  pub ascii: String,
+  /// The Unicode form, for display, like "münchen.de"
+  pub unicode: String,
+  /// The spelling of the input, when it differs from the Unicode form only in case or width,
+  /// like "MÜNCHEN.DE" or "ｍｕｎｃｈｅｎ．ｄｅ"
+  pub input: Option<String>,
+}
+
+/// The parts of the host in each of their spellings, see [crate::TLDExtract::forms]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExtractForms {
+  /// The "mirrors.tuna" part of "mirrors.tuna.tsinghua.edu.cn"
+  pub subdomain: Option<DomainForms>,
+  /// The "tsinghua" part of "mirrors.tuna.tsinghua.edu.cn"
+  pub domain: Option<DomainForms>,
+  /// The "edu.cn" part of "mirrors.tuna.tsinghua.edu.cn"
+  pub suffix: Option<DomainForms>,
+  /// The "tsinghua.edu.cn" part of "mirrors.tuna.tsinghua.edu.cn"
+  pub registered_domain: Option<DomainForms>,
+}
+
+impl ExtractForms {
+  pub(crate) fn new(extract_ref: &ExtractRef, idna: &IdnaOptions) -> Self {
+    let input = InputLabels::new(extract_ref);
+    let forms = |range: &Option<Range<usize>>| {
+      range.as_ref().map(|range| {
+        let ascii = &extract_ref.host[range.clone()];
+        let unicode = idna
+          .domain_to_unicode(ascii)
+          .unwrap_or_else(|| ascii.to_string());
+        let input = input
+          .part(&extract_ref.host, range)
+          .filter(|input| *input != unicode && same_but_case_or_width(input, &unicode))
+          .map(|input| input.to_string());
+        DomainForms {
+          ascii: ascii.to_string(),
+          unicode,
+          input,
+        }
+      })
+    };
+    ExtractForms {
+      subdomain: forms(&extract_ref.subdomain),
+      domain: forms(&extract_ref.domain),
+      suffix: forms(&extract_ref.suffix),
+      registered_domain: forms(&extract_ref.registered_domain),
+    }
+  }
+}
+
+/// The labels of the input, lined up with the labels of the normalized host
+struct InputLabels<'a> {
+  input: &'a str,
+  labels: Vec<Range<usize>>,
+}
+
+impl<'a> InputLabels<'a> {
+  fn new(extract_ref: &ExtractRef<'a>) -> Self {
+    let mut input = extract_ref
+      .input
+      .trim_matches(|ch: char| ch.is_whitespace() || ch <= ' ' || ch.is_control());
+    // 和规范化的时候一样去掉首尾的点
+    if extract_ref.fqdn {
+      input = input.strip_suffix(SEPARATORS).unwrap_or(input);
+    }
+    if extract_ref.leading_dot {
+      input = input.strip_prefix(SEPARATORS).unwrap_or(input);
+    }
+    let mut labels = Vec::new();
+    let mut start = 0;
+    for (index, ch) in input.char_indices() {
+      if SEPARATORS.contains(&ch) {
+        labels.push(start..index);
+        start = index + ch.len_utf8();
+      }
+    }
+    labels.push(start..input.len());
+    InputLabels { input, labels }
+  }
+  /// The input spelling of a part of the normalized host
+  fn part(&self, host: &str, range: &Range<usize>) -> Option<&'a str> {
+    // 标签数量不一样的时候无法对应
+    if self.labels.len() != host.split('.').count() {
+      return None;
+    }
+    let first = host[..range.start].matches('.').count();
+    let last = first + host[range.clone()].matches('.').count();
+    Some(&self.input[self.labels[first].start..self.labels[last].end])
+  }
+}
+
+/// Whether two spellings only differ in case or in full-width/half-width forms
+fn same_but_case_or_width(input: &str, unicode: &str) -> bool {
+  let fold = |ch: char| match ch {
+    // 全角的 ASCII 字符
+    '\u{FF01}'..='\u{FF5E}' => char::from_u32(ch as u32 - 0xFEE0).unwrap_or(ch),
+    ch if SEPARATORS.contains(&ch) => '.',
+    ch => ch,
+  };
+  input
+    .chars()
+    .flat_map(|ch| fold(ch).to_lowercase())
+    .eq(unicode.chars())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::TLDExtract;
+
+  fn forms_of(input: &str) -> ExtractForms {
+    let extract = TLDExtract::snapshot(false);
+    ExtractForms::new(&extract.extract_ref(input).unwrap(), &IdnaOptions::new())
+  }
+
+  fn input(forms: Option<DomainForms>) -> Option<String> {
+    forms.and_then(|forms| forms.input)
+  }
+
+  #[test]
+  fn fullwidth_separators() {
+    let forms = forms_of("ＷＷＷ．例子。中国");
+    let registered_domain = forms.registered_domain.unwrap();
+    assert_eq!(registered_domain.ascii, "xn--fsqu00a.xn--fiqs8s");
+    assert_eq!(registered_domain.unicode, "例子.中国");
+    assert_eq!(registered_domain.input.as_deref(), Some("例子。中国"));
+    assert_eq!(input(forms.subdomain).as_deref(), Some("ＷＷＷ"));
+    // 和 Unicode 形式一样的时候没有 input
+    assert_eq!(input(forms.suffix), None);
+  }
+
+  #[test]
+  fn stripped_dots() {
+    let forms = forms_of("WWW.Example.COM.");
+    assert_eq!(input(forms.suffix).as_deref(), Some("COM"));
+    assert_eq!(
+      input(forms.registered_domain).as_deref(),
+      Some("Example.COM")
+    );
+    let forms = forms_of(".WWW.Example.COM");
+    assert_eq!(input(forms.subdomain).as_deref(), Some("WWW"));
+    assert_eq!(input(forms.domain).as_deref(), Some("Example"));
+    // 全角的点也会去掉
+    let forms = forms_of("Example.COM．");
+    assert_eq!(
+      input(forms.registered_domain).as_deref(),
+      Some("Example.COM")
+    );
+  }
+
+  #[test]
+  fn label_count_mismatch() {
+    let extract = TLDExtract::snapshot(false);
+    let mut extract_ref = extract.extract_ref("WWW.Example.COM").unwrap();
+    assert_eq!(InputLabels::new(&extract_ref).labels.len(), 3);
+    // UTS #46 只把这几个分隔符映射成点，这里直接换掉输入，模拟标签数量对不上
+    extract_ref.input = "WWW.Example";
+    assert_eq!(InputLabels::new(&extract_ref).labels.len(), 2);
+    let forms = ExtractForms::new(&extract_ref, &IdnaOptions::new());
+    let registered_domain = forms.registered_domain.unwrap();
+    assert_eq!(registered_domain.unicode, "example.com");
+    assert_eq!(registered_domain.input, None);
+    assert_eq!(input(forms.subdomain), None);
+  }
+}
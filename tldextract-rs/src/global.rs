@@ -0,0 +1,68 @@
+use crate::error::Result;
+use crate::{ExtractResult, TLDExtract};
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+static GLOBAL: OnceLock<TLDExtract> = OnceLock::new();
+
+/// The process-wide extractor of the free functions, [TLDExtract::default] unless
+/// [set_global] installed another one first
+#[inline]
+pub fn global() -> &'static TLDExtract {
+  GLOBAL.get_or_init(TLDExtract::default)
+}
+
+/// Install the process-wide extractor, call it at startup before anything uses [global].
+///
+/// Returns the extractor back if one is already in place.
+/// ```rust
+/// use tldextract_rs::{Source, SuffixList, TLDExtract};
+/// let suffix = SuffixList::new(Source::Snapshot, true, None);
+/// let extract = TLDExtract::new(suffix, true).unwrap();
+/// assert!(tldextract_rs::set_global(extract).is_ok());
+/// // 不包含私有域名
+/// let e = tldextract_rs::extract("foo.github.io").unwrap();
+/// assert_eq!(e.registered_domain, Some("github.io".to_string()));
+/// ```
+pub fn set_global(extract: TLDExtract) -> std::result::Result<(), Box<TLDExtract>> {
+  GLOBAL.set(extract).map_err(Box::new)
+}
+
+/// [TLDExtract::extract] with the [global] extractor
+/// ```rust
+/// let e = tldextract_rs::extract("www.example.co.uk").unwrap();
+/// assert_eq!(e.domain, Some("example".to_string()));
+/// ```
+#[inline]
+pub fn extract(target: &str) -> Result<ExtractResult> {
+  global().extract(target)
+}
+
+/// [TLDExtract::registrable_domain] with the [global] extractor, normalized like [extract]
+/// ```rust
+/// let registered_domain = tldextract_rs::registered_domain("www.example.co.uk");
+/// assert_eq!(registered_domain.as_deref(), Some("example.co.uk"));
+/// // 和 extract 一样是规范形式
+/// let registered_domain = tldextract_rs::registered_domain("WWW.Example.CO.UK");
+/// assert_eq!(registered_domain.as_deref(), Some("example.co.uk"));
+/// let registered_domain = tldextract_rs::registered_domain("www.xn--fsqu00a.xn--fiqs8s");
+/// assert_eq!(registered_domain.as_deref(), Some("例子.中国"));
+/// ```
+#[inline]
+pub fn registered_domain(target: &str) -> Option<Cow<'_, str>> {
+  global().registrable_domain(target)
+}
+
+/// [TLDExtract::public_suffix] with the [global] extractor, normalized like [extract]
+/// ```rust
+/// use std::borrow::Cow;
+/// // 已经是规范形式的时候借用输入
+/// let public_suffix = tldextract_rs::public_suffix("www.example.co.uk");
+/// assert!(matches!(public_suffix, Some(Cow::Borrowed("co.uk"))));
+/// let public_suffix = tldextract_rs::public_suffix("www.Example.CO.UK");
+/// assert!(matches!(public_suffix, Some(Cow::Owned(s)) if s == "co.uk"));
+/// ```
+#[inline]
+pub fn public_suffix(target: &str) -> Option<Cow<'_, str>> {
+  global().public_suffix(target)
+}
@@ -0,0 +1,212 @@
+//! The code point rules of IDNA2008: the derived property of RFC 5892 section 3, and the
+//! CONTEXTJ and CONTEXTO rules of its Appendix A. The Unicode properties come from the
+//! data that `idna` already uses.
+use crate::IdnaErrorKind;
+use icu_properties::props::{
+  CanonicalCombiningClass, ChangesWhenNfkcCasefolded, DefaultIgnorableCodePoint, GeneralCategory,
+  HangulSyllableType, JoiningType, NoncharacterCodePoint, Script, WhiteSpace,
+};
+use icu_properties::{CodePointMapData, CodePointSetData};
+
+/// The derived property values of RFC 5892 section 2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Property {
+  Pvalid,
+  ContextJ,
+  ContextO,
+  Disallowed,
+  Unassigned,
+}
+
+/// RFC 5892 section 3
+fn derived_property(ch: char) -> Property {
+  // F. Exceptions
+  match ch {
+    '\u{df}' | '\u{3c2}' | '\u{6fd}' | '\u{6fe}' | '\u{f0b}' | '\u{3007}' => {
+      return Property::Pvalid
+    }
+    '\u{b7}' | '\u{375}' | '\u{5f3}' | '\u{5f4}' | '\u{30fb}' => return Property::ContextO,
+    '\u{660}'..='\u{669}' | '\u{6f0}'..='\u{6f9}' => return Property::ContextO,
+    '\u{640}' | '\u{7fa}' | '\u{302e}' | '\u{302f}' | '\u{3031}'..='\u{3035}' | '\u{303b}' => {
+      return Property::Disallowed
+    }
+    _ => {}
+  }
+  // G. BackwardCompatible 目前是空的
+  let category = CodePointMapData::<GeneralCategory>::new().get(ch);
+  let noncharacter = CodePointSetData::new::<NoncharacterCodePoint>().contains(ch);
+  if category == GeneralCategory::Unassigned && !noncharacter {
+    return Property::Unassigned;
+  }
+  if ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-' {
+    return Property::Pvalid;
+  }
+  if ch == '\u{200c}' || ch == '\u{200d}' {
+    return Property::ContextJ;
+  }
+  // B. Unstable，NFKC(casefold(NFKC(cp))) 和 cp 不一样
+  if CodePointSetData::new::<ChangesWhenNfkcCasefolded>().contains(ch) {
+    return Property::Disallowed;
+  }
+  // C. IgnorableProperties
+  if CodePointSetData::new::<DefaultIgnorableCodePoint>().contains(ch)
+    || CodePointSetData::new::<WhiteSpace>().contains(ch)
+    || noncharacter
+  {
+    return Property::Disallowed;
+  }
+  // D. IgnorableBlocks
+  if matches!(ch, '\u{20d0}'..='\u{20ff}' | '\u{1d100}'..='\u{1d24f}') {
+    return Property::Disallowed;
+  }
+  // I. OldHangulJamo
+  if matches!(
+    CodePointMapData::<HangulSyllableType>::new().get(ch),
+    HangulSyllableType::LeadingJamo
+      | HangulSyllableType::VowelJamo
+      | HangulSyllableType::TrailingJamo
+  ) {
+    return Property::Disallowed;
+  }
+  // A. LetterDigits
+  match category {
+    GeneralCategory::LowercaseLetter
+    | GeneralCategory::UppercaseLetter
+    | GeneralCategory::OtherLetter
+    | GeneralCategory::DecimalNumber
+    | GeneralCategory::ModifierLetter
+    | GeneralCategory::NonspacingMark
+    | GeneralCategory::SpacingMark => Property::Pvalid,
+    _ => Property::Disallowed,
+  }
+}
+
+/// Check the code points of a U-label, the first one that is not allowed and why
+pub(crate) fn check_label(label: &str) -> Result<(), IdnaErrorKind> {
+  let chars: Vec<char> = label.chars().collect();
+  for (i, &ch) in chars.iter().enumerate() {
+    let allowed = match derived_property(ch) {
+      Property::Pvalid => true,
+      Property::ContextJ => context_j(&chars, i),
+      Property::ContextO => context_o(&chars, i),
+      Property::Disallowed | Property::Unassigned => return Err(IdnaErrorKind::DisallowedChar(ch)),
+    };
+    if !allowed {
+      return Err(IdnaErrorKind::Context(ch));
+    }
+  }
+  Ok(())
+}
+
+/// RFC 5892 Appendix A.1 and A.2
+fn context_j(chars: &[char], i: usize) -> bool {
+  let ccc = CodePointMapData::<CanonicalCombiningClass>::new();
+  if i > 0 && ccc.get(chars[i - 1]) == CanonicalCombiningClass::Virama {
+    return true;
+  }
+  if chars[i] == '\u{200d}' {
+    return false;
+  }
+  // ZERO WIDTH NON-JOINER 在 {L,D} T* 和 T* {R,D} 之间
+  let joining_type = CodePointMapData::<JoiningType>::new();
+  let joins = |ch: &char, side: JoiningType| {
+    let jt = joining_type.get(*ch);
+    jt == side || jt == JoiningType::DualJoining
+  };
+  let not_transparent = |ch: &&char| joining_type.get(**ch) != JoiningType::Transparent;
+  let before = chars[..i].iter().rev().find(not_transparent);
+  let after = chars[i + 1..].iter().find(not_transparent);
+  before.is_some_and(|ch| joins(ch, JoiningType::LeftJoining))
+    && after.is_some_and(|ch| joins(ch, JoiningType::RightJoining))
+}
+
+/// RFC 5892 Appendix A.3 to A.9
+fn context_o(chars: &[char], i: usize) -> bool {
+  let script = CodePointMapData::<Script>::new();
+  let before = i.checked_sub(1).map(|b| chars[b]);
+  let after = chars.get(i + 1).copied();
+  match chars[i] {
+    // MIDDLE DOT，只能在 "l·l" 里
+    '\u{b7}' => before == Some('l') && after == Some('l'),
+    // GREEK LOWER NUMERAL SIGN，后面是希腊字母
+    '\u{375}' => after.is_some_and(|ch| script.get(ch) == Script::Greek),
+    // HEBREW PUNCTUATION GERESH / GERSHAYIM，前面是希伯来字母
+    '\u{5f3}' | '\u{5f4}' => before.is_some_and(|ch| script.get(ch) == Script::Hebrew),
+    // KATAKANA MIDDLE DOT，标签里有平假名、片假名或者汉字
+    '\u{30fb}' => chars.iter().any(|&ch| {
+      matches!(
+        script.get(ch),
+        Script::Hiragana | Script::Katakana | Script::Han
+      )
+    }),
+    // 两种阿拉伯数字不能混用
+    '\u{660}'..='\u{669}' => !chars.iter().any(|ch| matches!(ch, '\u{6f0}'..='\u{6f9}')),
+    '\u{6f0}'..='\u{6f9}' => !chars.iter().any(|ch| matches!(ch, '\u{660}'..='\u{669}')),
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn derived_properties() {
+    for (ch, property) in [
+      ('a', Property::Pvalid),
+      ('ü', Property::Pvalid),
+      ('例', Property::Pvalid),
+      ('ß', Property::Pvalid),
+      ('\u{200d}', Property::ContextJ),
+      ('\u{b7}', Property::ContextO),
+      ('\u{663}', Property::ContextO),
+      // 大写字母不稳定
+      ('Ü', Property::Disallowed),
+      // 符号不是字母或数字
+      ('☃', Property::Disallowed),
+      ('\u{640}', Property::Disallowed),
+      ('\u{1100}', Property::Disallowed),
+      ('\u{20d0}', Property::Disallowed),
+      ('\u{ad}', Property::Disallowed),
+      ('\u{fdd0}', Property::Disallowed),
+      ('\u{e0080}', Property::Unassigned),
+    ] {
+      assert_eq!(derived_property(ch), property, "{ch:?}");
+    }
+  }
+
+  #[test]
+  fn context_rules() {
+    for label in [
+      "l\u{b7}l",
+      "\u{375}α",
+      "א\u{5f3}",
+      "カ\u{30fb}",
+      "\u{660}\u{661}",
+      // 在 Virama 后面
+      "क\u{94d}\u{200d}",
+      "क\u{94d}\u{200c}",
+      // 阿拉伯字母之间，中间可以有透明的字符
+      "ب\u{200c}\u{64b}ب",
+    ] {
+      assert_eq!(check_label(label), Ok(()), "{label}");
+    }
+    for (label, ch) in [
+      ("a\u{b7}l", '\u{b7}'),
+      ("\u{375}a", '\u{375}'),
+      ("a\u{5f3}", '\u{5f3}'),
+      ("a\u{30fb}", '\u{30fb}'),
+      ("\u{660}\u{6f0}", '\u{660}'),
+      ("a\u{200d}", '\u{200d}'),
+      ("a\u{200c}b", '\u{200c}'),
+      ("\u{200c}ب", '\u{200c}'),
+    ] {
+      assert_eq!(
+        check_label(label),
+        Err(IdnaErrorKind::Context(ch)),
+        "{label}"
+      );
+    }
+    assert_eq!(check_label("a☃"), Err(IdnaErrorKind::DisallowedChar('☃')));
+  }
+}
@@ -0,0 +1,164 @@
+use crate::error::Result;
+use crate::TLDExtractError;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Parse an IPv6 literal, with or without the surrounding brackets.
+///
+/// Returns `Ok(None)` when the host does not look like an IPv6 literal at all.
+pub(crate) fn parse_ipv6(host: &str) -> Result<Option<IpAddr>> {
+  let literal = match host.strip_prefix('[') {
+    Some(h) => match h.strip_suffix(']') {
+      Some(h) => h,
+      None => return Err(TLDExtractError::InvalidIp(host.to_string())),
+    },
+    // 没有中括号的时候，至少要有两个冒号才可能是IPv6
+    None if host.matches(':').count() >= 2 => host,
+    None => return Ok(None),
+  };
+  match literal.parse::<Ipv6Addr>() {
+    Ok(ip) => Ok(Some(IpAddr::V6(ip))),
+    Err(_) => Err(TLDExtractError::InvalidIp(host.to_string())),
+  }
+}
+
+/// Parse an IPv4 host the way the WHATWG URL standard does.
+///
+/// Besides the dotted-decimal form this accepts hex (`0x7f.1`), octal (`0177.0.0.1`)
+/// and shorthand forms with fewer than four parts, where the last part fills the
+/// remaining bytes. Returns `Ok(None)` when the last label is not numeric, in which
+/// case the host is a domain name.
+pub(crate) fn parse_ipv4(host: &str) -> Result<Option<IpAddr>> {
+  // 允许末尾有一个点
+  let host_without_dot = match host.strip_suffix('.') {
+    Some(h) if !h.is_empty() => h,
+    _ => host,
+  };
+  match host_without_dot.rsplit('.').next() {
+    Some(last) if ends_in_number(last) => {}
+    _ => return Ok(None),
+  }
+  let err = || TLDExtractError::InvalidIp(host.to_string());
+  let mut numbers = [0_u64; 4];
+  let mut len = 0;
+  for part in host_without_dot.split('.') {
+    if len == numbers.len() {
+      return Err(err());
+    }
+    numbers[len] = parse_ipv4_number(part).ok_or_else(err)?;
+    len += 1;
+  }
+  let (last, head) = numbers[..len].split_last().ok_or_else(err)?;
+  // 超出范围的数字是无效的IPv4，不是域名
+  if head.iter().any(|n| *n > 255) || *last >= 256_u64.pow(5 - len as u32) {
+    return Err(err());
+  }
+  let mut ipv4 = *last;
+  for (index, n) in head.iter().enumerate() {
+    ipv4 += n << (8 * (3 - index));
+  }
+  Ok(Some(IpAddr::V4(Ipv4Addr::from(ipv4 as u32))))
+}
+
+fn ends_in_number(label: &str) -> bool {
+  if !label.is_empty() && label.chars().all(|ch| ch.is_ascii_digit()) {
+    return true;
+  }
+  parse_ipv4_number(label).is_some()
+}
+
+fn parse_ipv4_number(part: &str) -> Option<u64> {
+  if part.is_empty() {
+    return None;
+  }
+  let (digits, radix) =
+    if let Some(hex) = part.strip_prefix("0x").or_else(|| part.strip_prefix("0X")) {
+      (hex, 16)
+    } else if part.len() > 1 && part.starts_with('0') {
+      (&part[1..], 8)
+    } else {
+      (part, 10)
+    };
+  // "0x" 本身就是0
+  if digits.is_empty() {
+    return Some(0);
+  }
+  if !digits.chars().all(|ch| ch.is_digit(radix)) {
+    return None;
+  }
+  // 数字都合法，只可能是溢出，交给 parse_ipv4 的范围检查
+  Some(u64::from_str_radix(digits, radix).unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ipv4(host: &str) -> Option<IpAddr> {
+    parse_ipv4(host).unwrap()
+  }
+
+  #[test]
+  fn ipv4_forms() {
+    let localhost = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+    assert_eq!(ipv4("127.0.0.1"), localhost);
+    assert_eq!(ipv4("127.0.0.1."), localhost);
+    assert_eq!(ipv4("0x7f.1"), localhost);
+    assert_eq!(ipv4("0177.0.0.1"), localhost);
+    assert_eq!(ipv4("2130706433"), localhost);
+    assert_eq!(ipv4("0x7F000001"), localhost);
+    assert_eq!(ipv4("1.2.3"), Some(IpAddr::V4(Ipv4Addr::new(1, 2, 0, 3))));
+    assert_eq!(ipv4("1.0x"), Some(IpAddr::V4(Ipv4Addr::new(1, 0, 0, 0))));
+    assert_eq!(ipv4("4294967295"), Some(IpAddr::V4(Ipv4Addr::BROADCAST)));
+  }
+
+  #[test]
+  fn ipv4_failures() {
+    for host in [
+      "0x100000000",
+      "4294967296",
+      "99999999999",
+      "0xffffffffffffffffffff",
+      "99999999999999999999999",
+      "256.0.0.1",
+      "1.2.3.256",
+      "1.2.65536",
+      "1.2.3.4.5",
+      "09",
+      "a.1",
+      "1..2",
+    ] {
+      assert!(
+        matches!(parse_ipv4(host), Err(TLDExtractError::InvalidIp(h)) if h == host),
+        "{host}"
+      );
+    }
+  }
+
+  #[test]
+  fn not_ipv4() {
+    for host in ["example.com", "1.2.3.a", "0x1g", "1.example", "localhost"] {
+      assert_eq!(parse_ipv4(host).unwrap(), None, "{host}");
+    }
+  }
+
+  #[test]
+  fn ipv6_forms() {
+    let mapped = "::ffff:1.2.3.4".parse::<Ipv6Addr>().unwrap();
+    assert_eq!(
+      parse_ipv6("::ffff:1.2.3.4").unwrap(),
+      Some(IpAddr::V6(mapped))
+    );
+    assert_eq!(
+      parse_ipv6("[::1]").unwrap(),
+      Some(IpAddr::V6(Ipv6Addr::LOCALHOST))
+    );
+    assert_eq!(parse_ipv6("example.com").unwrap(), None);
+    assert_eq!(parse_ipv6("a:b").unwrap(), None);
+    for host in ["[::1", "[example.com]", "1::2::3", "[::ffff:1.2.3.256]"] {
+      assert!(
+        matches!(parse_ipv6(host), Err(TLDExtractError::InvalidIp(_))),
+        "{host}"
+      );
+    }
+  }
+}
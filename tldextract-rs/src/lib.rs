@@ -1,131 +1,387 @@
 //! Use the public suffix list to resolve the top-level domain name
 //!
 //! ## Examples
+//! ```rust
+//! let e = tldextract_rs::extract("https://www.example.co.uk/a").unwrap();
+//! assert_eq!(e.registered_domain, Some("example.co.uk".to_string()));
+//! assert_eq!(tldextract_rs::public_suffix("foo.github.io").as_deref(), Some("github.io"));
+//! ```
+//!
+//! With a suffix list of your own:
 //! ```rust,no_run
 //! use tldextract_rs::TLDExtract;
 //! let source = tldextract_rs::Source::Snapshot;
 //! let suffix = tldextract_rs::SuffixList::new(source, false, None);
-//! let mut extract = TLDExtract::new(suffix, true).unwrap();
+//! let extract = TLDExtract::new(suffix, true).unwrap();
 //! let e = extract.extract("  www.setup.zip");
 //! println!("{:#?}", e);
 //! ```
 #![warn(missing_docs)]
 
-pub use crate::suffix_list::{Source, SuffixList};
-pub use error::{Result, TLDExtractError};
+#[cfg(feature = "unicode-security")]
+pub use crate::confusable::{ConfusableAnalysis, Risk};
+pub use crate::suffix_list::{Provenance, SkippedLine, Source, SuffixList};
+pub use batch::ExtractLines;
+pub use domain::Domain;
+pub use error::{IdnaErrorKind, Result, TLDExtractError};
+pub use explain::{
+  Explain, ListedRule, MatchedRule, RuleOrigin, Section, SplitReason, VisitedNode,
+};
+pub use forms::{DomainForms, ExtractForms};
+pub use global::{extract, global, public_suffix, registered_domain, set_global};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::ops::Index;
+use std::net::IpAddr;
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, PoisonError, RwLock};
+pub use trie::{SuffixMatch, TLDTrieTree};
+pub use uts46::IdnaOptions;
 
+mod batch;
+#[cfg(feature = "reqwest")]
+mod cache;
+#[cfg(feature = "unicode-security")]
+mod confusable;
+mod domain;
 mod error;
+mod explain;
+mod forms;
+mod global;
+mod idna2008;
+mod ip;
 mod snapshot;
 mod suffix_list;
+mod trie;
+mod url;
+mod uts46;
+mod validate;
 
-/// TLDTrieTree
+/// ExtractResult
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
-#[derive(Debug, Clone)]
-pub struct TLDTrieTree {
-  // 节点
-  node: HashMap<String, TLDTrieTree>,
-  // 是否可以为顶级域名
-  end: bool,
+#[derive(Clone, Debug, Default)]
+pub struct ExtractResult {
+  /// The "mirrors.tuna" part of "mirrors.tuna.tsinghua.edu.cn"
+  pub subdomain: Option<String>,
+  /// The "tsinghua" part of "mirrors.tuna.tsinghua.edu.cn"
+  pub domain: Option<String>,
+  /// The "edu.cn" part of "mirrors.tuna.tsinghua.edu.cn"
+  pub suffix: Option<String>,
+  /// The "tsinghua.edu.cn" part of "mirrors.tuna.tsinghua.edu.cn"
+  pub registered_domain: Option<String>,
+  /// The parsed address when the host is an IPv4 or IPv6 literal, the other fields are empty
+  pub ip: Option<IpAddr>,
+  /// Whether the suffix matched a rule from the PRIVATE section, like "github.io"
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub is_private: bool,
+  /// Whether the TLD is not in the list and the implicit "*" rule was used, see [UnknownTld::Default]
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub is_default_rule: bool,
+  /// Whether the host ended with a dot, like the fully qualified "www.example.com."
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub fqdn: bool,
+  /// Whether the host started with a dot, like the cookie domain ".example.com"
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub leading_dot: bool,
+  /// The ASCII, Unicode and input spelling of each part, see [TLDExtract::forms]
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub forms: Option<ExtractForms>,
 }
 
-impl TLDTrieTree {
-  /// Insert TLDTrieTree Construction Data
-  #[inline]
-  fn insert(&mut self, keys: Vec<&str>) {
-    let keys_len = keys.len();
-    let mut current_node = &mut self.node;
-    for (index, mut key) in keys.clone().into_iter().enumerate() {
-      let mut is_exclude = false;
-      // 以!开头的需要排除掉
-      if index == keys_len - 1 && key.starts_with('!') {
-        key = &key[1..];
-        is_exclude = true;
-      }
-      // 获取下一个节点，没有就插入默认节点
-      let next_node = current_node.entry(key.to_string()).or_insert(TLDTrieTree {
-        node: Default::default(),
-        end: false,
-      });
-      // 当这是最后一个节点，设置可以为顶级域名
-      if !is_exclude && (index == keys_len - 1)
-                // 最后一个为*的，节点可以为顶级域名
-                || (key != "*" && index == keys_len - 2 && keys[index + 1] == "*")
-      {
-        next_node.end = true;
-      }
-      current_node = &mut next_node.node;
-    }
-  }
-  /// Search tree, return the maximum path searched
-  #[inline]
-  fn search(&self, keys: &[String]) -> Vec<Suffix> {
-    let mut suffix_list = Vec::new();
-    let mut current_node = &self.node;
-    for key in keys.iter() {
-      match current_node.get(key) {
-        Some(next_node) => {
-          suffix_list.push(Suffix {
-            suffix: key.to_string(),
-            end: next_node.end,
-          });
-          current_node = &next_node.node;
-        }
-        None => {
-          if let Some(next_node) = current_node.get("*") {
-            suffix_list.push(Suffix {
-              suffix: key.to_string(),
-              end: next_node.end,
-            });
-          }
-          break;
-        }
-      }
+/// What to do when the rightmost label of a host is not in the suffix list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownTld {
+  /// No suffix and no registered domain, "example.notatld" has the domain "notatld"
+  #[default]
+  Strict,
+  /// The implicit "*" rule of the PSL, "example.notatld" has the suffix "notatld"
+  Default,
+  /// Return a [TLDExtractError::UnknownTld]
+  Error,
+}
+
+impl FromStr for UnknownTld {
+  type Err = TLDExtractError;
+
+  fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "strict" => Ok(UnknownTld::Strict),
+      "default" => Ok(UnknownTld::Default),
+      "error" => Ok(UnknownTld::Error),
+      _ => Err(TLDExtractError::InvalidOption(s.to_string())),
     }
-    suffix_list
   }
 }
 
-#[derive(Debug)]
-struct Suffix {
-  suffix: String,
-  end: bool,
+/// ExtractRef, the borrowed form of [ExtractResult]
+///
+/// Every part is a slice of the normalized host. When the input is already normalized
+/// (lowercase ASCII), the host is borrowed from the input and extraction does not allocate.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExtractRef<'a> {
+  host: Cow<'a, str>,
+  subdomain: Option<Range<usize>>,
+  domain: Option<Range<usize>>,
+  suffix: Option<Range<usize>>,
+  registered_domain: Option<Range<usize>>,
+  ip: Option<IpAddr>,
+  suffix_match: SuffixMatch,
+  fqdn: bool,
+  leading_dot: bool,
+  // 规范化之前的输入
+  input: &'a str,
 }
 
-/// ExtractResult
-#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
-#[derive(Clone, Debug, Default)]
-pub struct ExtractResult {
+impl<'a> ExtractRef<'a> {
+  /// Split the host by the suffix found in the tree
+  fn split(self, suffix_match: SuffixMatch) -> Self {
+    let mut extract_ref = ExtractRef {
+      host: self.host,
+      suffix_match,
+      fqdn: self.fqdn,
+      leading_dot: self.leading_dot,
+      input: self.input,
+      ..Default::default()
+    };
+    let suffix_len = suffix_match.len;
+    let host = extract_ref.host.as_ref();
+    // 从右边数第n个标签的起始位置
+    let label_start = |n: usize| {
+      host
+        .rmatch_indices('.')
+        .nth(n - 1)
+        .map(|(index, _)| index + 1)
+        .unwrap_or(0)
+    };
+    let labels = host.split('.').count();
+    let end = host.len();
+    let mut domain_end = end;
+    if suffix_len > 0 {
+      let suffix_start = label_start(suffix_len);
+      extract_ref.suffix = Some(suffix_start..end);
+      domain_end = suffix_start.saturating_sub(1);
+    }
+    // 域名本身就是顶级域名
+    if labels == suffix_len {
+      return extract_ref;
+    }
+    let domain_start = label_start(suffix_len + 1);
+    if domain_start < domain_end {
+      extract_ref.domain = Some(domain_start..domain_end);
+    }
+    if domain_start > 1 {
+      extract_ref.subdomain = Some(0..domain_start - 1);
+    }
+    if suffix_len > 0 {
+      extract_ref.registered_domain = Some(domain_start..end);
+    }
+    extract_ref
+  }
+  fn part(&self, range: &Option<Range<usize>>) -> Option<&str> {
+    range.as_ref().map(|r| &self.host[r.clone()])
+  }
+  /// The normalized host, or the IP literal as it was written
+  pub fn host(&self) -> &str {
+    &self.host
+  }
   /// The "mirrors.tuna" part of "mirrors.tuna.tsinghua.edu.cn"
-  pub subdomain: Option<String>,
+  pub fn subdomain(&self) -> Option<&str> {
+    self.part(&self.subdomain)
+  }
   /// The "tsinghua" part of "mirrors.tuna.tsinghua.edu.cn"
-  pub domain: Option<String>,
+  pub fn domain(&self) -> Option<&str> {
+    self.part(&self.domain)
+  }
   /// The "edu.cn" part of "mirrors.tuna.tsinghua.edu.cn"
-  pub suffix: Option<String>,
+  pub fn suffix(&self) -> Option<&str> {
+    self.part(&self.suffix)
+  }
   /// The "tsinghua.edu.cn" part of "mirrors.tuna.tsinghua.edu.cn"
-  pub registered_domain: Option<String>,
+  pub fn registered_domain(&self) -> Option<&str> {
+    self.part(&self.registered_domain)
+  }
+  /// The parsed address when the host is an IPv4 or IPv6 literal
+  pub fn ip(&self) -> Option<IpAddr> {
+    self.ip
+  }
+  /// Whether the suffix matched a rule from the PRIVATE section
+  pub fn is_private(&self) -> bool {
+    self.suffix_match.is_private
+  }
+  /// Whether the TLD is not in the list and the implicit "*" rule was used
+  pub fn is_default_rule(&self) -> bool {
+    self.suffix_match.is_default_rule
+  }
+  /// Whether the host ended with a dot, which is not part of [ExtractRef::host]
+  pub fn fqdn(&self) -> bool {
+    self.fqdn
+  }
+  /// Whether the host started with a dot, which is not part of [ExtractRef::host]
+  pub fn leading_dot(&self) -> bool {
+    self.leading_dot
+  }
+  /// The split of the same host with the PRIVATE section left out,
+  /// "foo.user.github.io" has the suffix "io" and the registered domain "github.io"
+  pub fn icann(&self) -> ExtractRef<'_> {
+    let host = ExtractRef {
+      host: Cow::Borrowed(&self.host),
+      ip: self.ip,
+      fqdn: self.fqdn,
+      leading_dot: self.leading_dot,
+      input: self.input,
+      ..Default::default()
+    };
+    if self.ip.is_some() {
+      return host;
+    }
+    host.split(self.suffix_match.icann())
+  }
+}
+
+impl From<ExtractRef<'_>> for ExtractResult {
+  fn from(value: ExtractRef<'_>) -> Self {
+    ExtractResult {
+      subdomain: value.subdomain().map(|s| s.to_string()),
+      domain: value.domain().map(|s| s.to_string()),
+      suffix: value.suffix().map(|s| s.to_string()),
+      registered_domain: value.registered_domain().map(|s| s.to_string()),
+      ip: value.ip,
+      is_private: value.is_private(),
+      is_default_rule: value.is_default_rule(),
+      fqdn: value.fqdn,
+      leading_dot: value.leading_dot,
+      forms: None,
+    }
+  }
+}
+
+/// ExtractSplitResult
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Debug, Default)]
+pub struct ExtractSplitResult {
+  /// The split with private domains, "github.io" is the suffix of "foo.user.github.io"
+  pub private: ExtractResult,
+  /// The split with ICANN rules only, "io" is the suffix of "foo.user.github.io"
+  pub icann: ExtractResult,
+}
+
+/// ExtractUrlResult
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Debug, Default)]
+pub struct ExtractUrlResult {
+  /// The "https" part of "https://user:pw@www.example.co.uk:8443/a?b#c"
+  pub scheme: Option<String>,
+  /// The "user:pw" part of "https://user:pw@www.example.co.uk:8443/a?b#c"
+  pub userinfo: Option<String>,
+  /// The "www.example.co.uk" part of "https://user:pw@www.example.co.uk:8443/a?b#c"
+  pub host: String,
+  /// The "8443" part of "https://user:pw@www.example.co.uk:8443/a?b#c"
+  pub port: Option<u16>,
+  /// The "/a" part of "https://user:pw@www.example.co.uk:8443/a?b#c"
+  pub path: Option<String>,
+  /// The "b" part of "https://user:pw@www.example.co.uk:8443/a?b#c"
+  pub query: Option<String>,
+  /// The "c" part of "https://user:pw@www.example.co.uk:8443/a?b#c"
+  pub fragment: Option<String>,
+  /// The extract result of the host
+  pub extract: ExtractResult,
+}
+
+/// How strictly the host name is checked before it is split
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Validation {
+  /// Only letters, digits, "." and "-", and the host cannot start or end with "-"
+  #[default]
+  Lenient,
+  /// RFC 1035: no empty labels, labels up to 63 bytes, names up to 253 bytes,
+  /// and no label starts or ends with "-"
+  Rfc1035,
+  /// [Validation::Rfc1035], plus a label with "--" at positions 3 and 4 must be an "xn--"
+  /// A-label that round-trips through Punycode and UTS #46, and whose code points are allowed
+  /// by IDNA2008 (RFC 5892): no DISALLOWED or unassigned code points, and CONTEXTJ and
+  /// CONTEXTO code points only where their context rules allow them
+  Idna2008,
+}
+
+impl FromStr for Validation {
+  type Err = TLDExtractError;
+
+  fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "lenient" => Ok(Validation::Lenient),
+      "rfc1035" => Ok(Validation::Rfc1035),
+      "idna2008" => Ok(Validation::Idna2008),
+      _ => Err(TLDExtractError::InvalidOption(s.to_string())),
+    }
+  }
 }
 
 /// TLDExtract
+///
+/// `TLDExtract` is `Send + Sync`, one extractor can be shared by many threads (e.g. in an `Arc`).
+/// When the suffix list expires, the new trie is built on the side and swapped in atomically,
+/// so extraction never sees a half-built tree.
 #[derive(Debug)]
 pub struct TLDExtract {
-  suffix_list: SuffixList,
-  tld_trie: TLDTrieTree,
+  // None 表示使用编译时生成的快照
+  suffix_list: RwLock<Option<SuffixList>>,
+  built: RwLock<Option<Arc<BuiltTrie>>>,
   domain_to_unicode: bool,
+  unknown_tld: UnknownTld,
+  validation: Validation,
+  allow_underscore: bool,
+  reject_dots: bool,
+  forms: bool,
+  idna: IdnaOptions,
+}
+
+// 编译期检查，TLDExtract 不再是 Send + Sync 的话这里编译不过
+const _: () = {
+  const fn assert_send_sync<T: Send + Sync>() {}
+  assert_send_sync::<TLDExtract>();
+  assert_send_sync::<ExtractResult>();
+  assert_send_sync::<ExtractRef<'static>>();
+};
+
+/// A trie and where its rules came from, swapped as one so they always describe each other
+#[derive(Debug)]
+struct BuiltTrie {
+  trie: Arc<TLDTrieTree>,
+  /// The sources as [Explain::source] shows them
+  source: String,
+  /// `None` for a compiled trie
+  provenance: Option<Provenance>,
+  /// The rules as they are written in the list, empty for a compiled trie
+  listed_rules: HashMap<String, ListedRule>,
+}
+
+impl BuiltTrie {
+  fn build(suffix_list: &mut SuffixList) -> Result<Self> {
+    let trie = suffix_list.build()?;
+    // 来源链中实际提供数据的那个
+    let source = match &suffix_list.provenance {
+      Some(provenance) => provenance.to_string(),
+      None => suffix_list.source.to_string(),
+    };
+    let source = match &suffix_list.extra {
+      Some(extra) => format!("{source} + {extra}"),
+      None => source,
+    };
+    Ok(BuiltTrie {
+      trie: Arc::new(trie),
+      source,
+      provenance: suffix_list.provenance.clone(),
+      listed_rules: suffix_list.listed_rules.clone(),
+    })
+  }
 }
 
 impl Default for TLDExtract {
   fn default() -> Self {
-    let mut suffix = SuffixList::default();
-    let trie = suffix.build().expect("default trie build error");
-    TLDExtract {
-      suffix_list: suffix.clone(),
-      tld_trie: trie,
-      domain_to_unicode: true,
-    }
+    TLDExtract::snapshot(true)
   }
 }
 
@@ -134,30 +390,240 @@ impl TLDExtract {
   #[inline]
   pub fn new(suffix: SuffixList, domain_to_unicode: bool) -> Result<Self> {
     let mut new_suffix = suffix;
-    let trie = new_suffix.build()?;
+    let built = BuiltTrie::build(&mut new_suffix)?;
     Ok(TLDExtract {
-      suffix_list: new_suffix.clone(),
-      tld_trie: trie,
+      suffix_list: RwLock::new(Some(new_suffix)),
+      built: RwLock::new(Some(Arc::new(built))),
       domain_to_unicode,
+      unknown_tld: UnknownTld::Strict,
+      validation: Validation::Lenient,
+      allow_underscore: false,
+      reject_dots: false,
+      forms: false,
+      idna: IdnaOptions::new(),
     })
   }
-  /// update SuffixList
+  /// Creates a TLDExtract from the snapshot compiled at build time, without building anything.
+  ///
+  /// This is a `const fn`, so the extractor can live in a `static`:
+  /// ```rust
+  /// use tldextract_rs::TLDExtract;
+  /// static EXTRACT: TLDExtract = TLDExtract::snapshot(true);
+  /// let e = EXTRACT.extract("www.example.co.uk").unwrap();
+  /// assert_eq!(e.registered_domain, Some("example.co.uk".to_string()));
+  /// ```
+  #[inline]
+  pub const fn snapshot(domain_to_unicode: bool) -> Self {
+    TLDExtract {
+      suffix_list: RwLock::new(None),
+      built: RwLock::new(None),
+      domain_to_unicode,
+      unknown_tld: UnknownTld::Strict,
+      validation: Validation::Lenient,
+      allow_underscore: false,
+      reject_dots: false,
+      forms: false,
+      idna: IdnaOptions::new(),
+    }
+  }
+  /// set how hosts under a TLD that is not in the list are split, [UnknownTld::Strict] by default
+  /// ```rust
+  /// use tldextract_rs::{TLDExtract, UnknownTld};
+  /// let extract = TLDExtract::snapshot(true).unknown_tld(UnknownTld::Default);
+  /// let e = extract.extract("www.example.notatld").unwrap();
+  /// assert_eq!(e.registered_domain, Some("example.notatld".to_string()));
+  /// assert!(e.is_default_rule);
+  /// ```
+  #[inline]
+  pub const fn unknown_tld(mut self, unknown_tld: UnknownTld) -> Self {
+    self.unknown_tld = unknown_tld;
+    self
+  }
+  /// set how strictly host names are checked, [Validation::Lenient] by default
+  /// ```rust
+  /// use tldextract_rs::{TLDExtract, TLDExtractError, Validation};
+  /// let extract = TLDExtract::snapshot(true).validation(Validation::Rfc1035);
+  /// let e = extract.extract("a..example.com");
+  /// assert!(matches!(e, Err(TLDExtractError::EmptyLabel { offset: 2 })));
+  /// ```
+  #[inline]
+  pub const fn validation(mut self, validation: Validation) -> Self {
+    self.validation = validation;
+    self
+  }
+  /// set whether labels of the subdomain may contain "_", like "_dmarc" or "_sip._tcp" in DNS
+  /// records. The registered domain and the suffix still cannot.
+  /// ```rust
+  /// use tldextract_rs::TLDExtract;
+  /// let extract = TLDExtract::snapshot(true).allow_underscore(true);
+  /// let e = extract.extract("_sip._tcp.example.org").unwrap();
+  /// assert_eq!(e.subdomain, Some("_sip._tcp".to_string()));
+  /// assert!(extract.extract("www.my_site.org").is_err());
+  /// ```
   #[inline]
-  pub fn update(&mut self, suffix: Option<SuffixList>) {
-    if let Some(new_suffix) = suffix {
-      self.suffix_list = new_suffix;
-    }
-    let backup_tld_trie = self.tld_trie.clone();
-    match self.suffix_list.build() {
-      Ok(trie) => {
-        self.tld_trie = trie;
+  pub const fn allow_underscore(mut self, allow_underscore: bool) -> Self {
+    self.allow_underscore = allow_underscore;
+    self
+  }
+  /// set whether a trailing dot ("www.example.com.") or a leading dot (".example.com") is an
+  /// error. By default the dot is stripped and reported by [ExtractResult::fqdn] and
+  /// [ExtractResult::leading_dot].
+  /// ```rust
+  /// use tldextract_rs::TLDExtract;
+  /// let extract = TLDExtract::snapshot(true);
+  /// let e = extract.extract("www.example.com.").unwrap();
+  /// assert_eq!(e.registered_domain, Some("example.com".to_string()));
+  /// assert!(e.fqdn);
+  /// assert!(extract.reject_dots(true).extract(".example.com").is_err());
+  /// ```
+  #[inline]
+  pub const fn reject_dots(mut self, reject_dots: bool) -> Self {
+    self.reject_dots = reject_dots;
+    self
+  }
+  /// set whether [ExtractResult::forms] carries the ASCII and the Unicode form of each part,
+  /// and the input spelling when it differs only in case or width, from one extraction
+  /// ```rust
+  /// use tldextract_rs::TLDExtract;
+  /// let extract = TLDExtract::snapshot(false).forms(true);
+  /// let e = extract.extract("www.MÜNCHEN.de").unwrap();
+  /// let forms = e.forms.unwrap().registered_domain.unwrap();
+  /// assert_eq!(forms.ascii, "xn--mnchen-3ya.de");
+  /// assert_eq!(forms.unicode, "münchen.de");
+  /// assert_eq!(forms.input, Some("MÜNCHEN.de".to_string()));
+  /// ```
+  #[inline]
+  pub const fn forms(mut self, forms: bool) -> Self {
+    self.forms = forms;
+    self
+  }
+  /// set the IDNA options for hosts and for the rules of the suffix list.
+  ///
+  /// The suffix list is built again if its options differ, set [SuffixList::idna] before
+  /// [TLDExtract::new] to build it only once. The snapshot is always compiled with the defaults.
+  /// ```rust
+  /// use tldextract_rs::{IdnaOptions, TLDExtract};
+  /// let extract = TLDExtract::snapshot(true).idna(IdnaOptions::new().transitional(true));
+  /// let e = extract.extract("www.straße.de").unwrap();
+  /// assert_eq!(e.registered_domain, Some("strasse.de".to_string()));
+  /// ```
+  pub fn idna(mut self, idna: IdnaOptions) -> Self {
+    self.idna = idna;
+    let suffix_list = self
+      .suffix_list
+      .get_mut()
+      .unwrap_or_else(PoisonError::into_inner);
+    if let Some(old_suffix) = suffix_list.as_ref().filter(|s| s.idna != idna) {
+      let mut new_suffix = old_suffix.clone().idna(idna);
+      // 失败的话保留之前的数据，和 update 一样
+      if let Ok(built) = BuiltTrie::build(&mut new_suffix) {
+        *suffix_list = Some(new_suffix);
+        *self.built.get_mut().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(built));
       }
-      Err(_err) => {
-        // 恢复之前的数据
-        self.tld_trie = backup_tld_trie;
+    }
+    self
+  }
+  /// Creates a TLDExtract from a trie written by [TLDExtract::save_compiled], without building
+  /// anything. The file is read and copied into memory, not mapped. The trie is kept as it was
+  /// compiled, [TLDExtract::idna] does not rebuild it and [TLDExtract::update] only replaces it
+  /// when a new suffix list is given.
+  /// ```rust
+  /// use tldextract_rs::TLDExtract;
+  /// let path = std::env::temp_dir().join("tldextract-doc.trie");
+  /// TLDExtract::default().save_compiled(&path).unwrap();
+  /// let extract = TLDExtract::load_compiled(&path, true).unwrap();
+  /// let e = extract.extract("www.example.co.uk").unwrap();
+  /// assert_eq!(e.registered_domain, Some("example.co.uk".to_string()));
+  /// ```
+  pub fn load_compiled(path: impl AsRef<Path>, domain_to_unicode: bool) -> Result<Self> {
+    let trie = TLDTrieTree::from_compiled(&std::fs::read(path)?)?;
+    let mut extract = TLDExtract::snapshot(domain_to_unicode);
+    let built = BuiltTrie {
+      trie: Arc::new(trie),
+      source: "compiled".to_string(),
+      provenance: None,
+      listed_rules: HashMap::new(),
+    };
+    *extract
+      .built
+      .get_mut()
+      .unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(built));
+    Ok(extract)
+  }
+  /// Write the current trie to a file, see [TLDTrieTree::to_compiled]
+  pub fn save_compiled(&self, path: impl AsRef<Path>) -> Result<()> {
+    let bytes = self.with_trie(TLDTrieTree::to_compiled);
+    std::fs::write(path, bytes)?;
+    Ok(())
+  }
+  /// Where the rules of the current trie came from, `None` for the snapshot and a compiled trie
+  /// ```rust
+  /// use tldextract_rs::{Source, SuffixList, TLDExtract};
+  /// let source = Source::Chain(vec![Source::Text(String::new()), Source::Snapshot]);
+  /// let extract = TLDExtract::new(SuffixList::new(source, false, None), true).unwrap();
+  /// assert_eq!(extract.provenance().unwrap().to_string(), "snapshot");
+  /// ```
+  pub fn provenance(&self) -> Option<Provenance> {
+    self.built()?.provenance.clone()
+  }
+  /// update SuffixList, keep the current trie and suffix list if the build fails
+  /// ```rust
+  /// use tldextract_rs::{Source, SuffixList, TLDExtract};
+  /// let extract = TLDExtract::new(SuffixList::new(Source::Snapshot, false, None), true).unwrap();
+  /// extract.update(Some(SuffixList::new(Source::Local("/no/such/list.dat".into()), false, None)));
+  /// assert_eq!(extract.provenance().unwrap().to_string(), "snapshot");
+  /// ```
+  #[inline]
+  pub fn update(&self, suffix: Option<SuffixList>) {
+    let mut suffix_list = self
+      .suffix_list
+      .write()
+      .unwrap_or_else(PoisonError::into_inner);
+    // 快照不需要更新
+    if let Some(new_suffix) = suffix.or_else(|| suffix_list.clone()) {
+      self.rebuild(&mut suffix_list, new_suffix);
+    }
+  }
+  /// Rebuild the trie if the suffix list has expired, skip if another thread is already on it
+  fn update_if_expired(&self) {
+    let is_expired = match self.suffix_list.try_read() {
+      Ok(suffix_list) => suffix_list.as_ref().is_some_and(SuffixList::is_expired),
+      Err(_) => false,
+    };
+    if !is_expired {
+      return;
+    }
+    if let Ok(mut suffix_list) = self.suffix_list.try_write() {
+      // 拿到锁之后再检查一次，可能其他线程已经更新过了
+      if let Some(expired) = suffix_list.as_ref().filter(|s| s.is_expired()).cloned() {
+        self.rebuild(&mut suffix_list, expired);
       }
     }
   }
+  /// Build `new_suffix` on the side, and swap in the new trie and `new_suffix` if it builds
+  fn rebuild(&self, suffix_list: &mut Option<SuffixList>, mut new_suffix: SuffixList) {
+    // 失败的话保留之前的列表和树，它们的来源还是对得上
+    if let Ok(built) = BuiltTrie::build(&mut new_suffix) {
+      *self.built.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(built));
+      *suffix_list = Some(new_suffix);
+    }
+  }
+  /// Run `f` with the current trie, it stays valid even if the trie is swapped by an update
+  fn with_trie<R>(&self, f: impl FnOnce(&TLDTrieTree) -> R) -> R {
+    f(self.trie().as_deref().unwrap_or(TLDTrieTree::snapshot()))
+  }
+  /// The current trie, `None` for the snapshot
+  fn trie(&self) -> Option<Arc<TLDTrieTree>> {
+    self.built().map(|built| built.trie.clone())
+  }
+  /// The current trie and where it came from, `None` for the snapshot
+  fn built(&self) -> Option<Arc<BuiltTrie>> {
+    self
+      .built
+      .read()
+      .unwrap_or_else(PoisonError::into_inner)
+      .clone()
+  }
 }
 
 ///                    hierarchical part
@@ -169,76 +635,345 @@ impl TLDExtract {
 // scheme  user information     host     port                  query         fragment
 ///
 impl TLDExtract {
-  /// TLDExtract extract
+  /// TLDExtract extract, the target can be a bare host or a full URL
+  #[inline]
+  pub fn extract(&self, target: &str) -> Result<ExtractResult> {
+    let extract_ref = self.extract_ref(target)?;
+    Ok(self.to_result(&extract_ref))
+  }
+  /// Extract both the private-aware and the ICANN-only split of the target with one lookup
+  #[inline]
+  pub fn extract_split(&self, target: &str) -> Result<ExtractSplitResult> {
+    let extract_ref = self.extract_ref(target)?;
+    Ok(ExtractSplitResult {
+      private: self.to_result(&extract_ref),
+      icann: self.to_result(&extract_ref.icann()),
+    })
+  }
+  /// Like [TLDExtract::extract], but borrows every part from the normalized input
   #[inline]
-  pub fn extract(&mut self, target: &str) -> Result<ExtractResult> {
-    // 先检查域名是否有效
-    let target = match idna::domain_to_ascii(target) {
+  pub fn extract_ref<'a>(&self, target: &'a str) -> Result<ExtractRef<'a>> {
+    let parts = url::parse(target)?;
+    self.extract_host(parts.host)
+  }
+  /// Like [TLDExtract::extract], but returns a [Domain] to walk up the host name.
+  /// IP literals are a [TLDExtractError::NotDomain]
+  /// ```rust
+  /// use tldextract_rs::TLDExtract;
+  /// let extract = TLDExtract::default();
+  /// let domain = extract.domain("https://www.example.co.uk/a").unwrap();
+  /// assert!(!domain.is_registrable());
+  /// assert!(domain.parent().unwrap().is_registrable());
+  /// assert!(extract.domain("127.0.0.1").is_err());
+  /// ```
+  #[inline]
+  pub fn domain(&self, target: &str) -> Result<Domain> {
+    let parts = url::parse(target)?;
+    self.update_if_expired();
+    // 父域名用同一棵树查找后缀
+    let trie = self.trie();
+    let tree = trie.as_deref().unwrap_or(TLDTrieTree::snapshot());
+    let extract_ref = self.extract_host_in(tree, parts.host)?;
+    Domain::new(&extract_ref, trie.clone(), self.unknown_tld)
+  }
+  /// Whether the target is a public suffix itself, like "github.io" or "co.uk"
+  /// ```rust
+  /// use std::borrow::Cow;
+  /// use tldextract_rs::TLDExtract;
+  /// let extract = TLDExtract::default();
+  /// assert!(extract.is_public_suffix("github.io"));
+  /// assert!(!extract.is_public_suffix("user.github.io"));
+  /// assert!(extract.is_registrable_domain("user.github.io"));
+  /// assert_eq!(extract.public_suffix("www.Example.CO.UK").as_deref(), Some("co.uk"));
+  /// assert_eq!(
+  ///   extract.registrable_domain("www.Example.CO.UK").as_deref(),
+  ///   Some("example.co.uk")
+  /// );
+  /// // 和 extract 一样转成 Unicode
+  /// let registrable = extract.registrable_domain("www.xn--fsqu00a.xn--fiqs8s");
+  /// assert_eq!(registrable.as_deref(), Some("例子.中国"));
+  /// // 已经是规范形式的时候不分配内存
+  /// assert!(matches!(extract.public_suffix("www.example.co.uk"), Some(Cow::Borrowed("co.uk"))));
+  /// assert!(!extract.has_known_tld("example.notatld"));
+  /// ```
+  pub fn is_public_suffix(&self, target: &str) -> bool {
+    self
+      .lookup(target)
+      .is_some_and(|r| r.suffix.is_some() && r.registered_domain.is_none())
+  }
+  /// Whether the target is a registered domain itself, like "example.co.uk"
+  pub fn is_registrable_domain(&self, target: &str) -> bool {
+    self
+      .lookup(target)
+      .is_some_and(|r| r.registered_domain.is_some() && r.subdomain.is_none())
+  }
+  /// The public suffix of the target, normalized the way [TLDExtract::extract] returns it.
+  ///
+  /// Borrowed from the target when it is already normalized, `None` when there is no suffix
+  pub fn public_suffix<'a>(&self, target: &'a str) -> Option<Cow<'a, str>> {
+    let extract_ref = self.lookup(target)?;
+    let range = extract_ref.suffix.clone()?;
+    Some(self.normalized_part(extract_ref.host, range))
+  }
+  /// The registered domain of the target, normalized, see [TLDExtract::public_suffix]
+  pub fn registrable_domain<'a>(&self, target: &'a str) -> Option<Cow<'a, str>> {
+    let extract_ref = self.lookup(target)?;
+    let range = extract_ref.registered_domain.clone()?;
+    Some(self.normalized_part(extract_ref.host, range))
+  }
+  /// Whether the TLD of the target is in the suffix list, whatever [UnknownTld] says
+  pub fn has_known_tld(&self, target: &str) -> bool {
+    let host = match url::parse(target).and_then(|parts| self.normalize_host(parts.host)) {
+      Ok(host) if host.ip.is_none() => host,
+      _ => return false,
+    };
+    self.update_if_expired();
+    self.with_trie(|trie| trie.search(&host.host).len > 0)
+  }
+  /// The split of the target for the predicates, `None` if it is not a valid host
+  fn lookup<'a>(&self, target: &'a str) -> Option<ExtractRef<'a>> {
+    self.extract_ref(target).ok()
+  }
+  /// A part of the normalized host, decoding PunyCode if enabled
+  fn normalized_part<'a>(&self, host: Cow<'a, str>, range: Range<usize>) -> Cow<'a, str> {
+    let part = match host {
+      Cow::Borrowed(host) => Cow::Borrowed(&host[range]),
+      Cow::Owned(host) => Cow::Owned(host[range].to_string()),
+    };
+    if self.domain_to_unicode && part.split('.').any(|label| label.starts_with("xn--")) {
+      return Cow::Owned(self.domain_to_unicode(part.into_owned()));
+    }
+    part
+  }
+  /// Like [TLDExtract::extract], but also returns the other parts of the URL
+  #[inline]
+  pub fn extract_url(&self, target: &str) -> Result<ExtractUrlResult> {
+    let parts = url::parse(target)?;
+    let extract_ref = self.extract_host(parts.host)?;
+    Ok(ExtractUrlResult {
+      scheme: parts.scheme.map(|s| s.to_lowercase()),
+      userinfo: parts.userinfo.map(|s| s.to_string()),
+      host: parts.host.to_string(),
+      port: parts.port,
+      path: parts.path.map(|s| s.to_string()),
+      query: parts.query.map(|s| s.to_string()),
+      fragment: parts.fragment.map(|s| s.to_string()),
+      extract: self.to_result(&extract_ref),
+    })
+  }
+  /// Explain how the target is split: the rules that matched, the one that won and why
+  ///
+  /// ```rust
+  /// use tldextract_rs::{RuleOrigin, SplitReason, TLDExtract};
+  /// let extract = TLDExtract::default();
+  /// let explain = extract.explain("www.city.kobe.jp").unwrap();
+  /// assert_eq!(explain.prevailing.unwrap().rule, "!city.kobe.jp");
+  /// assert_eq!(explain.reason, SplitReason::Exception);
+  /// // 规则原来的写法和来源
+  /// let rule = extract.explain("foo.个人.hk").unwrap().prevailing.unwrap();
+  /// assert_eq!(rule.rule, "xn--ciqpn.hk");
+  /// assert_eq!((rule.text.as_str(), rule.origin), ("个人.hk", Some(RuleOrigin::Main)));
+  /// ```
+  pub fn explain(&self, target: &str) -> Result<Explain> {
+    let parts = url::parse(target)?;
+    let host = self.normalize_host(parts.host)?;
+    let labels = host.host.split('.').map(|l| l.to_string()).collect();
+    self.update_if_expired();
+    // 树、来源和规则的写法来自同一次构建，不需要等正在进行的更新
+    let built = self.built();
+    let source = match &built {
+      Some(built) => built.source.clone(),
+      None => Source::Snapshot.to_string(),
+    };
+    if host.ip.is_some() {
+      return Ok(Explain {
+        host: host.host.to_string(),
+        labels: Vec::new(),
+        source,
+        visited: Vec::new(),
+        rules: Vec::new(),
+        prevailing: None,
+        reason: SplitReason::Ip,
+        result: self.to_result(&host),
+      });
+    }
+    // 从文件加载的树不知道规则原来的写法，listed_rules 是空的
+    let (trie, listed_rules) = match &built {
+      Some(built) => (&*built.trie, &built.listed_rules),
+      None => (TLDTrieTree::snapshot(), SuffixList::snapshot_rules()),
+    };
+    let mut trace = trie.trace(&host.host);
+    trace.annotate(listed_rules);
+    let suffix = self.apply_unknown_tld(&host.host, trace.suffix_match)?;
+    let prevailing = trace.prevailing().cloned();
+    let reason = match &prevailing {
+      _ if suffix.is_default_rule => SplitReason::DefaultRule,
+      _ if suffix.len == 0 => SplitReason::UnknownTld,
+      Some(rule) if rule.is_exception => SplitReason::Exception,
+      Some(_) => SplitReason::LongestRule,
+      None => SplitReason::ImplicitRule,
+    };
+    let extract_ref = host.split(suffix);
+    self.check_underscore(&extract_ref)?;
+    Ok(Explain {
+      host: extract_ref.host.to_string(),
+      labels,
+      source,
+      visited: trace.visited,
+      rules: trace.rules,
+      prevailing,
+      reason,
+      result: self.to_result(&extract_ref),
+    })
+  }
+  fn extract_host<'a>(&self, target: &'a str) -> Result<ExtractRef<'a>> {
+    self.update_if_expired();
+    self.with_trie(|trie| self.extract_host_in(trie, target))
+  }
+  /// Like [TLDExtract::extract], with a trie that is already at hand
+  fn extract_in(&self, trie: &TLDTrieTree, target: &str) -> Result<ExtractResult> {
+    let parts = url::parse(target)?;
+    let extract_ref = self.extract_host_in(trie, parts.host)?;
+    Ok(self.to_result(&extract_ref))
+  }
+  fn extract_host_in<'a>(&self, trie: &TLDTrieTree, target: &'a str) -> Result<ExtractRef<'a>> {
+    let host = self.normalize_host(target)?;
+    if host.ip.is_some() {
+      return Ok(host);
+    }
+    let suffix = trie.search(&host.host);
+    let suffix = self.apply_unknown_tld(&host.host, suffix)?;
+    let extract_ref = host.split(suffix);
+    self.check_underscore(&extract_ref)?;
+    Ok(extract_ref)
+  }
+  /// Check and normalize the host, not split yet. The address is set if it is an IP literal
+  fn normalize_host<'a>(&self, target: &'a str) -> Result<ExtractRef<'a>> {
+    if let Some(ip) = ip::parse_ipv6(target)? {
+      return Ok(ExtractRef {
+        host: Cow::Borrowed(target),
+        ip: Some(ip),
+        input: target,
+        ..Default::default()
+      });
+    }
+    let input = target;
+    // 先检查域名是否有效，已经是规范形式的不会重新分配
+    let target = match self.idna.domain_to_ascii(target) {
       Ok(target) => target,
-      Err(err) => {
-        return Err(TLDExtractError::DomainError(err.to_string()));
+      Err(_) => return Err(TLDExtractError::idna(target, &self.idna)),
+    };
+    let (target, fqdn, leading_dot) = match target {
+      Cow::Borrowed(t) => {
+        let (t, fqdn, leading_dot) = strip_host(t);
+        (Cow::Borrowed(t), fqdn, leading_dot)
       }
+      Cow::Owned(t) => {
+        let (t, fqdn, leading_dot) = strip_host(&t);
+        (Cow::Owned(t.to_string()), fqdn, leading_dot)
+      }
+    };
+    if self.reject_dots && leading_dot {
+      return Err(TLDExtractError::EmptyLabel { offset: 0 });
+    }
+    if self.reject_dots && fqdn {
+      // 末尾的点后面是一个空标签
+      return Err(TLDExtractError::EmptyLabel {
+        offset: target.len() + 1,
+      });
+    }
+    let host = ExtractRef {
+      ip: ip::parse_ipv4(&target)?,
+      host: target,
+      fqdn,
+      leading_dot,
+      input,
+      ..Default::default()
     };
-    let target = target
-      .trim_matches(|ch: char| ch.is_whitespace() || ch <= ' ' || ch.is_control())
-      .to_string();
-    for (index, ch) in target.chars().enumerate() {
-      if !ch.is_ascii_alphanumeric() && ch != '.' && ch != '-'
-        || ((index == 0 || index == target.len() - 1) && ch == '-')
+    if host.ip.is_some() {
+      return Ok(host);
+    }
+    let target = &host.host;
+    for (offset, ch) in target.char_indices() {
+      if !ch.is_ascii_alphanumeric()
+        && ch != '.'
+        && ch != '-'
+        && !(ch == '_' && self.allow_underscore)
       {
-        return Err(TLDExtractError::DomainError(format!("char:{ch}")));
+        return Err(TLDExtractError::InvalidChar { offset, ch });
       }
-    }
-    // target.chars().map(|ch| ch.is_alphanumeric());
-    let keys: Vec<String> = target.rsplit('.').map(|s| s.to_string()).collect();
-    let mut extract_result = ExtractResult::default();
-    if self.suffix_list.is_expired() {
-      self.update(None);
-    }
-    let mut suffix_list = self.tld_trie.search(&keys);
-    let rev_key: Vec<String> = keys.clone().into_iter().rev().collect();
-    let rev_key = rev_key.as_slice();
-    let mut sl = Vec::new();
-    while let Some(s) = suffix_list.pop() {
-      if s.end {
-        sl.push(s.suffix);
-        while let Some(s) = suffix_list.pop() {
-          sl.push(s.suffix);
-        }
+      if (offset == 0 || offset == target.len() - 1) && ch == '-' {
+        return Err(TLDExtractError::Hyphen { offset });
       }
     }
-    if !sl.is_empty() {
-      let suffix = self.domain_to_unicode(sl.join("."));
-      extract_result.suffix = Some(suffix);
+    validate::validate(target, self.validation)?;
+    Ok(host)
+  }
+  /// Only the subdomain may contain "_", see [TLDExtract::allow_underscore]
+  fn check_underscore(&self, extract_ref: &ExtractRef) -> Result<()> {
+    if !self.allow_underscore {
+      return Ok(());
     }
-    // 域名本身就是顶级域名
-    if keys.len() == sl.len() {
-      return Ok(extract_result);
+    let start = extract_ref.subdomain.as_ref().map_or(0, |r| r.end);
+    match extract_ref.host[start..].find('_') {
+      Some(index) => Err(TLDExtractError::InvalidChar {
+        offset: start + index,
+        ch: '_',
+      }),
+      None => Ok(()),
     }
-    // 顶级域名的分界线索引
-    let index = rev_key.len() - sl.len() - 1;
-    let domain = self.domain_to_unicode(rev_key.index(index).to_string());
-    if !domain.is_empty() {
-      extract_result.domain = Some(domain);
+  }
+  /// Handle a TLD that is not in the list, see [UnknownTld]
+  fn apply_unknown_tld(&self, host: &str, suffix: SuffixMatch) -> Result<SuffixMatch> {
+    if suffix.len > 0 {
+      return Ok(suffix);
     }
-    let subdomain = self.domain_to_unicode(rev_key[..index].join("."));
-    let registered_domain = self.domain_to_unicode(rev_key[index..].join("."));
-    if !subdomain.is_empty() {
-      extract_result.subdomain = Some(subdomain);
+    match self.unknown_tld {
+      UnknownTld::Strict => Ok(suffix),
+      UnknownTld::Default => Ok(suffix.default_rule()),
+      UnknownTld::Error => {
+        let tld = host.rsplit('.').next().unwrap_or_default();
+        Err(TLDExtractError::UnknownTld(tld.to_string()))
+      }
     }
-    if !sl.is_empty() {
-      extract_result.registered_domain = Some(registered_domain);
+  }
+  /// Convert to the owned result, decoding PunyCode if enabled
+  fn to_result(&self, extract_ref: &ExtractRef) -> ExtractResult {
+    let to_unicode = |s: Option<&str>| s.map(|s| self.domain_to_unicode(s.to_string()));
+    ExtractResult {
+      subdomain: to_unicode(extract_ref.subdomain()),
+      domain: to_unicode(extract_ref.domain()),
+      suffix: to_unicode(extract_ref.suffix()),
+      registered_domain: to_unicode(extract_ref.registered_domain()),
+      ip: extract_ref.ip(),
+      is_private: extract_ref.is_private(),
+      is_default_rule: extract_ref.is_default_rule(),
+      fqdn: extract_ref.fqdn(),
+      leading_dot: extract_ref.leading_dot(),
+      forms: (self.forms && extract_ref.ip.is_none())
+        .then(|| ExtractForms::new(extract_ref, &self.idna)),
     }
-    Ok(extract_result)
   }
   /// If domain name conversion to PunyCode is enabled, the domain name will be re encoded
   fn domain_to_unicode(&self, mut domain: String) -> String {
     if self.domain_to_unicode {
-      let (unicode, err) = idna::domain_to_unicode(&domain);
-      if err.is_ok() && !unicode.is_empty() {
+      if let Some(unicode) = self.idna.domain_to_unicode(&domain) {
         domain = unicode;
       }
     }
     domain
   }
 }
+
+/// Trim whitespace, then strip one trailing dot and one leading dot, reporting whether they were there
+fn strip_host(host: &str) -> (&str, bool, bool) {
+  let host = host.trim_matches(|ch: char| ch.is_whitespace() || ch <= ' ' || ch.is_control());
+  let (host, fqdn) = match host.strip_suffix('.') {
+    Some(h) => (h, true),
+    None => (host, false),
+  };
+  match host.strip_prefix('.') {
+    Some(h) => (h, fqdn, true),
+    None => (host, fqdn, false),
+  }
+}
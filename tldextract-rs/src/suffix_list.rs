@@ -1,16 +1,19 @@
 use super::error::Result;
+#[cfg(feature = "reqwest")]
+use crate::cache::Fetched;
+use crate::explain::{ListedRule, RuleOrigin};
 use crate::snapshot::PUBLIC_SUFFIX_LIST;
+use crate::trie::{rule_to_ascii, ListParser, TLDTrieBuilder};
+use crate::IdnaOptions;
 use crate::TLDExtractError;
 use crate::TLDTrieTree;
-#[cfg(feature = "reqwest")]
-use reqwest::IntoUrl;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io::BufRead;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::OnceLock;
 use std::time::SystemTime;
 
-const PUBLIC_PRIVATE_SUFFIX_SEPARATOR: &str = "// ===BEGIN PRIVATE DOMAINS===";
 #[cfg(feature = "reqwest")]
 const PUBLIC_SUFFIX_LIST_URLS: &[&str] = &[
   "https://publicsuffix.org/list/public_suffix_list.dat",
@@ -30,6 +33,11 @@ pub enum Source {
   /// Read from remote URL，NONE default: PUBLIC_SUFFIX_LIST_URLS
   #[cfg(feature = "reqwest")]
   Remote(Option<reqwest::Url>),
+  /// The cached copy of a remote URL, without asking the server, see [SuffixList::cache_dir]
+  #[cfg(feature = "reqwest")]
+  Cache(Option<reqwest::Url>),
+  /// Try each source in turn until one supplies rules, like remote, then cache, then snapshot
+  Chain(Vec<Source>),
 }
 
 impl FromStr for Source {
@@ -40,29 +48,90 @@ impl FromStr for Source {
       "snapshot" => Ok(Source::Snapshot),
       #[cfg(feature = "reqwest")]
       "remote" => Ok(Source::Remote(None)),
+      #[cfg(feature = "reqwest")]
+      "cache" => Ok(Source::Cache(None)),
       _ => {
-        if let Ok(p) = <&str as TryInto<PathBuf>>::try_into(s) {
-          return if p.exists() {
-            Ok(Source::Local(p))
-          } else {
-            Err(TLDExtractError::Io(std::io::Error::new(
-              std::io::ErrorKind::NotFound,
-              "file does not exist",
-            )))
-          };
-        };
+        let p = PathBuf::from(s);
+        if p.exists() {
+          return Ok(Source::Local(p));
+        }
+        // "remote,cache,snapshot"
+        if s.contains(',') {
+          let sources = s.split(',').map(|s| Source::from_str(s.trim()));
+          return Ok(Source::Chain(sources.collect::<Result<_>>()?));
+        }
         #[cfg(feature = "reqwest")]
         if let Ok(u) = s.try_into() {
           return Ok(Source::Remote(Some(u)));
         }
-        Ok(Source::Snapshot)
+        Err(TLDExtractError::Io(std::io::Error::new(
+          std::io::ErrorKind::NotFound,
+          "file does not exist",
+        )))
       }
     }
   }
 }
 
+impl std::fmt::Display for Source {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Source::Text(_) => write!(f, "text"),
+      Source::Snapshot => write!(f, "snapshot"),
+      Source::Local(path) => write!(f, "{}", path.display()),
+      #[cfg(feature = "reqwest")]
+      Source::Remote(None) => write!(f, "remote"),
+      #[cfg(feature = "reqwest")]
+      Source::Remote(Some(url)) => write!(f, "{url}"),
+      #[cfg(feature = "reqwest")]
+      Source::Cache(None) => write!(f, "cache"),
+      #[cfg(feature = "reqwest")]
+      Source::Cache(Some(url)) => write!(f, "cache of {url}"),
+      Source::Chain(sources) => {
+        let sources: Vec<String> = sources.iter().map(|s| s.to_string()).collect();
+        write!(f, "{}", sources.join(","))
+      }
+    }
+  }
+}
+
+/// Where the rules of the last [SuffixList::build] came from
+#[derive(Debug, Clone)]
+pub struct Provenance {
+  /// The source that supplied the rules, the member of a [Source::Chain] that did
+  pub source: Source,
+  /// Whether a remote list was read from the cache, because the server could not be reached
+  /// or [Source::Cache] was asked for
+  pub from_cache: bool,
+  /// When the rules were last known to be current: the time of the download or revalidation,
+  /// the modification time of a local file, `None` for the snapshot and text
+  pub updated: Option<SystemTime>,
+}
+
+/// A line of the list that is not a valid rule, skipped by [SuffixList::build]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedLine {
+  /// The source the line was read from
+  pub origin: RuleOrigin,
+  /// Line number, starting from 1
+  pub line: usize,
+  /// The line
+  pub text: String,
+}
+
+impl std::fmt::Display for Provenance {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match (&self.source, self.from_cache) {
+      #[cfg(feature = "reqwest")]
+      (Source::Cache(_), _) => write!(f, "{}", self.source),
+      (source, true) => write!(f, "{source} (cached)"),
+      (source, false) => write!(f, "{source}"),
+    }
+  }
+}
+
 /// Mainly implementing the resolution and classification of domain names
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 pub struct SuffixList {
   /// Source [Source]
   pub source: Source,
@@ -78,6 +147,27 @@ pub struct SuffixList {
   pub expire: Option<std::time::Duration>,
   /// Last update time
   pub last_update: std::time::Duration,
+  /// How the rules are normalized, see [IdnaOptions]
+  pub idna: IdnaOptions,
+  /// Where remote lists are cached with their `ETag` and `Last-Modified`, `None` for no cache
+  #[cfg(feature = "reqwest")]
+  pub cache_dir: Option<PathBuf>,
+  /// Where the rules of the last build came from, `None` before the first build
+  pub provenance: Option<Provenance>,
+  /// The rules of the last build as they are written in the list, by their ASCII form
+  pub listed_rules: HashMap<String, ListedRule>,
+  /// Fail the build with [TLDExtractError::BadListLine] instead of skipping a line that is
+  /// not a valid rule
+  pub strict: bool,
+  /// The lines of the last build that are not valid rules and were skipped
+  pub skipped_lines: Vec<SkippedLine>,
+}
+
+impl Default for SuffixList {
+  /// The snapshot, with the same defaults as [SuffixList::new], like the cache directory
+  fn default() -> Self {
+    SuffixList::new(Source::default(), false, None)
+  }
 }
 
 impl SuffixList {
@@ -96,6 +186,13 @@ impl SuffixList {
       disable_private_domains,
       expire,
       last_update: now(),
+      idna: IdnaOptions::new(),
+      #[cfg(feature = "reqwest")]
+      cache_dir: crate::cache::default_cache_dir(),
+      provenance: None,
+      listed_rules: Default::default(),
+      strict: false,
+      skipped_lines: Vec::new(),
     }
   }
   /// set disable_private_domains
@@ -116,6 +213,36 @@ impl SuffixList {
     self.source = source;
     self
   }
+  /// set idna options
+  #[inline]
+  pub fn idna(mut self, idna: IdnaOptions) -> Self {
+    self.idna = idna;
+    self
+  }
+  /// set whether a line that is not a valid rule fails the build, see [SuffixList::skipped_lines]
+  /// ```rust
+  /// use tldextract_rs::{Source, SuffixList};
+  /// let source = Source::Text("com\nexa\u{e000}mple.com\n".to_string());
+  /// let mut suffix = SuffixList::new(source, false, None);
+  /// assert!(suffix.build().is_ok());
+  /// assert_eq!(suffix.skipped_lines[0].line, 2);
+  /// assert!(suffix.strict(true).build().is_err());
+  /// ```
+  #[inline]
+  pub fn strict(mut self, strict: bool) -> Self {
+    self.strict = strict;
+    self
+  }
+  /// set the cache directory of remote lists, `$XDG_CACHE_HOME/tldextract-rs` by default.
+  ///
+  /// A cached list is revalidated with `If-None-Match` / `If-Modified-Since`, and used as is
+  /// when the fetch fails.
+  #[cfg(feature = "reqwest")]
+  #[inline]
+  pub fn cache_dir(mut self, cache_dir: Option<PathBuf>) -> Self {
+    self.cache_dir = cache_dir;
+    self
+  }
   /// set extra source
   #[inline]
   pub fn extra(mut self, extra: Source) -> Self {
@@ -136,114 +263,204 @@ impl SuffixList {
   fn reset(&mut self) {
     self.private_suffixes = HashSet::new();
     self.public_suffixes = HashSet::new();
+    self.listed_rules = HashMap::new();
+    self.skipped_lines = Vec::new();
   }
-  fn parse_source(&mut self, source: Source) -> Result<()> {
-    let mut is_private_suffix = false;
-    let mut tld_lines = Vec::new();
-    match source {
+  fn parse_source(&mut self, source: Source, origin: RuleOrigin) -> Result<Provenance> {
+    let mut provenance = Provenance {
+      source: source.clone(),
+      from_cache: false,
+      updated: None,
+    };
+    #[cfg(feature = "reqwest")]
+    let mut unsaved = None;
+    let tld_lines: Vec<String> = match source {
       Source::Local(path) => {
-        let file = std::fs::File::open(path)?;
-        let lines = std::io::BufReader::new(file)
-          .lines()
-          .map(|l| l.unwrap_or_default());
-        tld_lines = lines.collect();
+        let file = std::fs::File::open(&path)?;
+        provenance.updated = file.metadata().and_then(|m| m.modified()).ok();
+        let lines = std::io::BufReader::new(file).lines();
+        lines.collect::<std::io::Result<_>>()?
       }
       #[cfg(feature = "reqwest")]
-      Source::Remote(u) => match u {
-        Some(u) => {
-          tld_lines = get_source_from_url(u)?;
-        }
-        None => {
-          let mut tld_err = TLDExtractError::SuffixListError(String::new());
-          for u in PUBLIC_SUFFIX_LIST_URLS {
-            match get_source_from_url(u.trim()) {
-              Ok(lines) => {
-                tld_lines = lines;
-                break;
-              }
-              Err(err) => {
-                tld_err = err;
-              }
-            }
-          }
-          if tld_lines.is_empty() {
-            return Err(tld_err);
-          }
-        }
-      },
-      Source::Snapshot => {
-        let lines = PUBLIC_SUFFIX_LIST.lines().map(|s| s.to_string());
-        for line in lines {
-          is_private_suffix = self.process_line(line, is_private_suffix);
-        }
+      Source::Remote(u) => {
+        let (url, fetched) = self.fetch_any(u, crate::cache::fetch)?;
+        provenance.source = Source::Remote(Some(url));
+        provenance.from_cache = fetched.from_cache;
+        provenance.updated = fetched.updated;
+        unsaved = fetched.unsaved;
+        fetched.lines
+      }
+      #[cfg(feature = "reqwest")]
+      Source::Cache(u) => {
+        let (url, fetched) = self.fetch_any(u, crate::cache::cached)?;
+        provenance.source = Source::Cache(Some(url));
+        provenance.from_cache = true;
+        provenance.updated = fetched.updated;
+        fetched.lines
       }
-      Source::Text(text) => {
-        let lines = text.lines().map(|s| s.to_string());
-        for line in lines {
-          is_private_suffix = self.process_line(line, is_private_suffix);
+      Source::Snapshot => PUBLIC_SUFFIX_LIST.lines().map(|s| s.to_string()).collect(),
+      Source::Text(text) => text.lines().map(|s| s.to_string()).collect(),
+      Source::Chain(sources) => return self.parse_chain(sources, origin),
+    };
+    #[cfg(feature = "reqwest")]
+    let len = self.public_suffixes.len() + self.private_suffixes.len();
+    let mut parser = ListParser::default();
+    for (index, line) in tld_lines.iter().enumerate() {
+      self.process_line(&mut parser, line, index + 1, origin)?;
+    }
+    // 下载的列表解析出了规则才写进缓存
+    #[cfg(feature = "reqwest")]
+    if self.public_suffixes.len() + self.private_suffixes.len() > len {
+      if let Some(unsaved) = unsaved {
+        unsaved.save();
+      }
+    }
+    Ok(provenance)
+  }
+  /// The first source of the chain that supplies rules, the rules of a failed one are dropped
+  fn parse_chain(&mut self, sources: Vec<Source>, origin: RuleOrigin) -> Result<Provenance> {
+    let mut chain_err = TLDExtractError::EmptyList;
+    for source in sources {
+      // 失败之前可能已经加了一部分规则，先备份
+      let public_suffixes = self.public_suffixes.clone();
+      let private_suffixes = self.private_suffixes.clone();
+      let listed_rules = self.listed_rules.clone();
+      let skipped_lines = self.skipped_lines.clone();
+      let len = public_suffixes.len() + private_suffixes.len();
+      match self.parse_source(source, origin) {
+        Ok(provenance) if self.public_suffixes.len() + self.private_suffixes.len() > len => {
+          return Ok(provenance);
         }
+        Ok(_) => chain_err = TLDExtractError::EmptyList,
+        Err(err) => chain_err = err,
       }
+      self.public_suffixes = public_suffixes;
+      self.private_suffixes = private_suffixes;
+      self.listed_rules = listed_rules;
+      self.skipped_lines = skipped_lines;
     }
-    for line in tld_lines {
-      is_private_suffix = self.process_line(line, is_private_suffix);
+    Err(chain_err)
+  }
+  /// Fetch the URL, or the first of PUBLIC_SUFFIX_LIST_URLS that works
+  #[cfg(feature = "reqwest")]
+  fn fetch_any(
+    &self,
+    u: Option<reqwest::Url>,
+    fetch: fn(reqwest::Url, Option<&std::path::Path>) -> Result<Fetched>,
+  ) -> Result<(reqwest::Url, Fetched)> {
+    let urls = match u {
+      Some(u) => vec![u],
+      None => PUBLIC_SUFFIX_LIST_URLS
+        .iter()
+        .filter_map(|u| reqwest::Url::parse(u.trim()).ok())
+        .collect(),
+    };
+    let mut tld_err = TLDExtractError::EmptyList;
+    for url in urls {
+      match fetch(url.clone(), self.cache_dir.as_deref()) {
+        Ok(fetched) if !fetched.lines.is_empty() => return Ok((url, fetched)),
+        Ok(_) => tld_err = TLDExtractError::EmptyList,
+        Err(err) => tld_err = err,
+      }
     }
-    Ok(())
+    Err(tld_err)
   }
-  ///  build TLDTrieTree
+  ///  build TLDTrieTree, and record in [SuffixList::provenance] where the rules came from
+  /// ```rust
+  /// use tldextract_rs::{Source, SuffixList};
+  /// let missing = Source::Local("/no/such/list.dat".into());
+  /// let source = Source::Chain(vec![missing, Source::Snapshot]);
+  /// let mut suffix = SuffixList::new(source, false, None);
+  /// let tree = suffix.build().unwrap();
+  /// assert_eq!(tree.search("example.co.uk").len, 2);
+  /// assert!(matches!(suffix.provenance.unwrap().source, Source::Snapshot));
+  /// ```
   #[inline]
   pub fn build(&mut self) -> Result<TLDTrieTree> {
     self.reset();
-    self.parse_source(self.source.clone())?;
+    let provenance = self.parse_source(self.source.clone(), RuleOrigin::Main)?;
     if let Some(extra) = self.extra.clone() {
-      self.parse_source(extra)?;
+      self.parse_source(extra, RuleOrigin::Extra)?;
     }
     let ttt = self.construct_tree();
     self.last_update = now();
+    self.provenance = Some(provenance);
     Ok(ttt)
   }
   // 处理行
-  fn process_line(&mut self, raw_line: String, mut is_private_suffix: bool) -> bool {
+  fn process_line(
+    &mut self,
+    parser: &mut ListParser,
+    line: &str,
+    line_number: usize,
+    origin: RuleOrigin,
+  ) -> Result<()> {
+    let (rule, is_private_suffix) = match parser.rule(line) {
+      Some(rule) => rule,
+      None => return Ok(()),
+    };
     // 已经到了私有域名分界线了，而且没有开启私有域名，直接跳过
     if is_private_suffix && self.disable_private_domains {
-      return is_private_suffix;
-    }
-    let line = raw_line.trim_end();
-    if !is_private_suffix && PUBLIC_PRIVATE_SUFFIX_SEPARATOR == line {
-      is_private_suffix = true;
+      return Ok(());
     }
-    if line.is_empty() || line.starts_with("//") {
-      return is_private_suffix;
-    }
-    if let Ok(suffix) = idna::domain_to_ascii(line) {
-      if is_private_suffix {
-        self.private_suffixes.insert(suffix.clone());
-        if suffix != line {
-          self.private_suffixes.insert(line.to_string());
-        }
-      } else {
-        self.public_suffixes.insert(suffix.clone());
-        if suffix != line {
-          self.public_suffixes.insert(suffix);
-        }
+    let suffix = rule_to_ascii(rule, |r| {
+      self.idna.domain_to_ascii(r).ok().map(|a| a.into_owned())
+    });
+    let suffix = match suffix {
+      Some(suffix) => suffix,
+      None if self.strict => {
+        return Err(TLDExtractError::BadListLine {
+          line: line_number,
+          text: rule.to_string(),
+        })
+      }
+      // 一行有问题不影响整个列表，记下来跳过
+      None => {
+        self.skipped_lines.push(SkippedLine {
+          origin,
+          line: line_number,
+          text: rule.to_string(),
+        });
+        return Ok(());
       }
+    };
+    // 同一条规则出现多次的时候记录第一次
+    self
+      .listed_rules
+      .entry(suffix.clone())
+      .or_insert_with(|| ListedRule {
+        text: rule.to_string(),
+        origin,
+      });
+    if is_private_suffix {
+      self.private_suffixes.insert(suffix);
+    } else {
+      self.public_suffixes.insert(suffix);
     }
-    is_private_suffix
+    Ok(())
+  }
+  /// The rules of the snapshot as they are written, parsed the first time they are needed
+  pub(crate) fn snapshot_rules() -> &'static HashMap<String, ListedRule> {
+    static SNAPSHOT_RULES: OnceLock<HashMap<String, ListedRule>> = OnceLock::new();
+    SNAPSHOT_RULES.get_or_init(|| {
+      let mut suffix = SuffixList::default();
+      // 快照里的规则都是有效的
+      let _ = suffix.parse_source(Source::Snapshot, RuleOrigin::Main);
+      suffix.listed_rules
+    })
   }
   // 构造前缀树
   fn construct_tree(&self) -> TLDTrieTree {
-    let mut trie_tree = TLDTrieTree {
-      node: Default::default(),
-      end: false,
-    };
-    let mut suffix_list = self.public_suffixes.clone();
-    if !self.disable_private_domains {
-      suffix_list.extend(self.private_suffixes.clone());
+    let mut trie_tree = TLDTrieBuilder::default();
+    for suffix in self.public_suffixes.iter() {
+      trie_tree.insert(suffix, false);
     }
-    for suffix in suffix_list {
-      let sp: Vec<&str> = suffix.rsplit('.').collect();
-      trie_tree.insert(sp);
+    if !self.disable_private_domains {
+      for suffix in self.private_suffixes.iter() {
+        trie_tree.insert(suffix, true);
+      }
     }
-    trie_tree
+    trie_tree.flatten().into()
   }
 }
 
@@ -252,14 +469,3 @@ fn now() -> std::time::Duration {
     .duration_since(std::time::UNIX_EPOCH)
     .unwrap_or_default()
 }
-
-#[cfg(feature = "reqwest")]
-fn get_source_from_url<T>(u: T) -> Result<Vec<String>>
-where
-  T: IntoUrl,
-{
-  let response = reqwest::blocking::get(u)?;
-  let bytes = response.bytes()?;
-  let lines = bytes.lines().map(|l| l.unwrap_or_default());
-  Ok(lines.collect())
-}
@@ -0,0 +1,365 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+use crate::explain::{MatchedRule, Section, Trace, VisitedNode};
+
+pub(crate) use builder::{fnv1a, rule_to_ascii, ListParser, TLDTrieBuilder};
+use builder::{label_hash, FlatTrie};
+use builder::{HAS_WILDCARD, ICANN_EXCEPTION, ICANN_RULE, PRIVATE_EXCEPTION, PRIVATE_RULE};
+
+mod builder;
+mod compiled;
+
+// build.rs 根据快照生成的树
+include!(concat!(env!("OUT_DIR"), "/snapshot_trie.rs"));
+
+static SNAPSHOT_TRIE: TLDTrieTree = TLDTrieTree {
+  nodes: Cow::Borrowed(&SNAPSHOT_NODES),
+  hashes: Cow::Borrowed(&SNAPSHOT_HASHES),
+  labels: Cow::Borrowed(SNAPSHOT_LABELS),
+};
+
+/// TLDTrieTree
+///
+/// All nodes live in one array, the children of a node are stored next to each other and
+/// sorted by the hash of their label, so a lookup is a binary search over a slice of integers.
+/// Labels are interned in one string, `com` is stored once no matter how many rules end with it.
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone)]
+pub struct TLDTrieTree {
+  // 节点，0是根节点
+  nodes: Cow<'static, [TLDTrieNode]>,
+  // 每个节点标签的哈希，和 nodes 一一对应
+  hashes: Cow<'static, [u64]>,
+  // 所有标签拼接在一起
+  labels: Cow<'static, str>,
+}
+
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, Copy, Default)]
+struct TLDTrieNode {
+  // 标签在 labels 中的位置
+  label_start: u32,
+  label_end: u32,
+  // 子节点在 nodes 中的位置
+  children_start: u32,
+  children_end: u32,
+  // 在这里结束的规则，见 builder 中的常量
+  flags: u8,
+}
+
+/// Shorthand for the generated snapshot tree
+const fn node(
+  label_start: u32,
+  label_end: u32,
+  children_start: u32,
+  children_end: u32,
+  flags: u8,
+) -> TLDTrieNode {
+  TLDTrieNode {
+    label_start,
+    label_end,
+    children_start,
+    children_end,
+    flags,
+  }
+}
+
+/// The suffix found by [TLDTrieTree::search]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SuffixMatch {
+  /// How many labels from the right make up the suffix, 0 if the TLD is not in the list
+  pub len: usize,
+  /// Whether the prevailing rule is from the PRIVATE section of the list
+  pub is_private: bool,
+  /// How many labels make up the suffix when only the ICANN section counts
+  pub icann_len: usize,
+  /// Whether the TLD is not in the list and the implicit `*` rule was used, see [SuffixMatch::default_rule]
+  pub is_default_rule: bool,
+}
+
+impl SuffixMatch {
+  /// The same match with the PRIVATE section left out
+  #[inline]
+  pub fn icann(&self) -> SuffixMatch {
+    SuffixMatch {
+      len: self.icann_len,
+      is_private: false,
+      icann_len: self.icann_len,
+      is_default_rule: self.is_default_rule,
+    }
+  }
+  /// Apply the implicit `*` rule to a TLD that is not in the list, the TLD becomes the suffix
+  #[inline]
+  pub fn default_rule(&self) -> SuffixMatch {
+    if self.len > 0 {
+      return *self;
+    }
+    SuffixMatch {
+      len: 1,
+      is_private: false,
+      icann_len: 1,
+      is_default_rule: true,
+    }
+  }
+}
+
+/// The rules of one section that matched so far
+#[derive(Debug, Clone, Copy, Default)]
+struct Prevailing {
+  // 最长的普通规则
+  rule: usize,
+  // 最长的例外规则
+  exception: usize,
+}
+
+impl Prevailing {
+  #[inline]
+  fn matched(&mut self, len: usize, flags: u8, rule: u8, exception: u8) {
+    if flags & rule != 0 {
+      self.rule = self.rule.max(len);
+    }
+    if flags & exception != 0 {
+      self.exception = self.exception.max(len);
+    }
+  }
+  /// Exception rules beat every other rule, and the suffix is the rule without its leftmost label
+  #[inline]
+  fn suffix_len(&self) -> usize {
+    if self.exception > 0 {
+      self.exception - 1
+    } else {
+      self.rule
+    }
+  }
+}
+
+impl From<FlatTrie> for TLDTrieTree {
+  fn from(value: FlatTrie) -> Self {
+    let nodes = value
+      .nodes
+      .into_iter()
+      .map(|n| {
+        node(
+          n.label_start,
+          n.label_end,
+          n.children_start,
+          n.children_end,
+          n.flags,
+        )
+      })
+      .collect();
+    TLDTrieTree {
+      nodes,
+      hashes: Cow::Owned(value.hashes),
+      labels: Cow::Owned(value.labels),
+    }
+  }
+}
+
+impl TLDTrieTree {
+  /// The tree of the embedded snapshot (private domains included), compiled at build time
+  #[inline]
+  pub const fn snapshot() -> &'static TLDTrieTree {
+    &SNAPSHOT_TRIE
+  }
+  /// Search tree from the rightmost label, following the [Public Suffix List algorithm](https://github.com/publicsuffix/list/wiki/Format#formal-algorithm):
+  /// exception rules win, otherwise the rule with the most labels wins, and a TLD that is in
+  /// the list but has no matching rule falls back to the implicit `*` rule.
+  #[inline]
+  pub fn search(&self, host: &str) -> SuffixMatch {
+    let mut icann = Prevailing::default();
+    let mut private = Prevailing::default();
+    self.visit(self.root(), Labels::new(host), 0, &mut |len, node| {
+      icann.matched(len, node.flags, ICANN_RULE, ICANN_EXCEPTION);
+      private.matched(len, node.flags, PRIVATE_RULE, PRIVATE_EXCEPTION);
+    });
+    let all = Prevailing {
+      rule: icann.rule.max(private.rule),
+      exception: icann.exception.max(private.exception),
+    };
+    let is_private = if all.exception > 0 {
+      private.exception > icann.exception
+    } else {
+      private.rule > icann.rule
+    };
+    // 没有规则匹配，但顶级域名在列表中，使用默认规则 *
+    let is_listed = || {
+      Labels::new(host)
+        .next()
+        .is_some_and(|tld| self.child(self.root(), tld).is_some())
+    };
+    let implicit = |len: usize| if len == 0 && is_listed() { 1 } else { len };
+    SuffixMatch {
+      len: implicit(all.suffix_len()),
+      is_private,
+      icann_len: implicit(icann.suffix_len()),
+      is_default_rule: false,
+    }
+  }
+  /// Like [TLDTrieTree::search], but also records every node visited and every rule matched
+  pub(crate) fn trace(&self, host: &str) -> Trace {
+    let mut trace = Trace {
+      suffix_match: self.search(host),
+      ..Default::default()
+    };
+    let mut path: Vec<&str> = Vec::new();
+    self.visit(self.root(), Labels::new(host), 0, &mut |depth, node| {
+      // 深度优先遍历，path 中剩下的就是当前节点的祖先
+      path.truncate(depth - 1);
+      path.push(self.label(node));
+      let rule = path.iter().rev().copied().collect::<Vec<_>>().join(".");
+      let flags = [
+        (ICANN_RULE, Section::Icann, false),
+        (ICANN_EXCEPTION, Section::Icann, true),
+        (PRIVATE_RULE, Section::Private, false),
+        (PRIVATE_EXCEPTION, Section::Private, true),
+      ];
+      for (flag, section, is_exception) in flags {
+        if node.flags & flag != 0 {
+          let rule = if is_exception {
+            format!("!{rule}")
+          } else {
+            rule.clone()
+          };
+          trace.rules.push(MatchedRule {
+            text: rule.clone(),
+            rule,
+            origin: None,
+            labels: depth,
+            section,
+            is_exception,
+          });
+        }
+      }
+      trace.visited.push(VisitedNode {
+        depth,
+        label: self.label(node).to_string(),
+        path: rule,
+      });
+    });
+    trace
+  }
+  /// Walk every path that matches the host, `*` matches any label
+  fn visit<'t>(
+    &'t self,
+    node: &'t TLDTrieNode,
+    mut labels: Labels,
+    depth: usize,
+    matched: &mut impl FnMut(usize, &'t TLDTrieNode),
+  ) {
+    let key = match labels.next() {
+      Some(key) => key,
+      None => return,
+    };
+    let wildcard = if node.flags & HAS_WILDCARD != 0 && key != "*" {
+      self.child(node, "*")
+    } else {
+      None
+    };
+    for next_node in [self.child(node, key), wildcard].into_iter().flatten() {
+      matched(depth + 1, next_node);
+      self.visit(next_node, labels.clone(), depth + 1, matched);
+    }
+  }
+  #[inline]
+  fn root(&self) -> &TLDTrieNode {
+    &self.nodes[0]
+  }
+  #[inline]
+  fn label(&self, node: &TLDTrieNode) -> &str {
+    &self.labels[node.label_start as usize..node.label_end as usize]
+  }
+  #[inline]
+  fn child(&self, node: &TLDTrieNode, key: &str) -> Option<&TLDTrieNode> {
+    let range = node.children_start as usize..node.children_end as usize;
+    let children = &self.nodes[range.clone()];
+    // 子节点少的时候直接比较标签
+    if children.len() <= 8 {
+      return children.iter().find(|child| self.label(child) == key);
+    }
+    let hashes = &self.hashes[range];
+    let hash = label_hash(key);
+    let index = hashes.partition_point(|h| *h < hash);
+    // 哈希冲突的时候再比较标签
+    hashes[index..]
+      .iter()
+      .take_while(|h| **h == hash)
+      .zip(children[index..].iter())
+      .map(|(_, child)| child)
+      .find(|child| self.label(child) == key)
+  }
+}
+
+/// Labels of a host from right to left, like `host.rsplit('.')`
+#[derive(Clone)]
+struct Labels<'a> {
+  host: &'a str,
+  end: Option<usize>,
+}
+
+impl<'a> Labels<'a> {
+  #[inline]
+  fn new(host: &'a str) -> Self {
+    Labels {
+      host,
+      end: Some(host.len()),
+    }
+  }
+}
+
+impl<'a> Iterator for Labels<'a> {
+  type Item = &'a str;
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    let end = self.end?;
+    let bytes = &self.host.as_bytes()[..end];
+    match bytes.iter().rposition(|b| *b == b'.') {
+      Some(dot) => {
+        self.end = Some(dot);
+        Some(&self.host[dot + 1..end])
+      }
+      None => {
+        self.end = None;
+        Some(&self.host[..end])
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Source, SuffixList};
+
+  #[test]
+  fn snapshot_matches_runtime_build() {
+    // build.rs 和 SuffixList 用同一份解析代码，生成的树必须完全一样
+    let tree = SuffixList::new(Source::Snapshot, false, None)
+      .build()
+      .unwrap();
+    assert_eq!(tree.to_compiled(), TLDTrieTree::snapshot().to_compiled());
+  }
+
+  #[test]
+  fn list_parser() {
+    let mut parser = builder::ListParser::default();
+    assert_eq!(parser.rule("// comment"), None);
+    assert_eq!(parser.rule(""), None);
+    assert_eq!(parser.rule("com  "), Some(("com", false)));
+    assert_eq!(parser.rule(builder::PRIVATE_SECTION_START), None);
+    assert_eq!(parser.rule("github.io"), Some(("github.io", true)));
+    let to_ascii = |r: &str| idna::domain_to_ascii(r).ok();
+    assert_eq!(
+      rule_to_ascii("*.个人.hk", to_ascii).as_deref(),
+      Some("*.xn--ciqpn.hk")
+    );
+    assert_eq!(
+      rule_to_ascii("!city.kobe.jp", to_ascii).as_deref(),
+      Some("!city.kobe.jp")
+    );
+    assert_eq!(rule_to_ascii("*", to_ascii).as_deref(), Some("*"));
+    assert_eq!(rule_to_ascii("a b", |_| None), None);
+  }
+}
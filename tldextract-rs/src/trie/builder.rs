@@ -0,0 +1,171 @@
+//! Build the suffix tree, shared with `build.rs` so it only depends on std
+use std::collections::{HashMap, VecDeque};
+
+/// 64-bit FNV-1a, also the checksum of compiled tries and the file name of cached lists
+#[inline]
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+    (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+  })
+}
+
+/// FNV-1a, labels are short and the hash only has to order siblings
+#[inline]
+pub(crate) fn label_hash(label: &str) -> u64 {
+  fnv1a(label.as_bytes())
+}
+
+/// The comment that starts the PRIVATE section of the list
+pub(crate) const PRIVATE_SECTION_START: &str = "// ===BEGIN PRIVATE DOMAINS===";
+
+/// Read the list line by line and keep track of the section,
+/// `SuffixList` and `build.rs` both parse the list with it
+#[derive(Debug, Default)]
+pub(crate) struct ListParser {
+  // 已经过了私有域名分界线
+  private: bool,
+}
+
+impl ListParser {
+  /// The rule on the line and whether it is in the PRIVATE section,
+  /// `None` for blank lines and comments
+  pub(crate) fn rule<'a>(&mut self, line: &'a str) -> Option<(&'a str, bool)> {
+    let line = line.trim_end();
+    if line == PRIVATE_SECTION_START {
+      self.private = true;
+    }
+    if line.is_empty() || line.starts_with("//") {
+      return None;
+    }
+    Some((line, self.private))
+  }
+}
+
+/// The rule with its labels in ASCII, `to_ascii` converts the part after the `!` and
+/// the leading `*.` labels, which are kept as they are since STD3 rules do not allow them
+pub(crate) fn rule_to_ascii(
+  rule: &str,
+  to_ascii: impl FnOnce(&str) -> Option<String>,
+) -> Option<String> {
+  let (exception, rule) = match rule.strip_prefix('!') {
+    Some(r) => ("!", r),
+    None => ("", rule),
+  };
+  let mut wildcards = String::new();
+  let mut rule = rule;
+  while let Some(r) = rule.strip_prefix("*.") {
+    wildcards.push_str("*.");
+    rule = r;
+  }
+  if rule == "*" {
+    return Some(format!("{exception}{wildcards}*"));
+  }
+  let ascii = to_ascii(rule)?;
+  Some(format!("{exception}{wildcards}{ascii}"))
+}
+
+/// A normal rule from the ICANN section ends at this node
+pub(crate) const ICANN_RULE: u8 = 1;
+/// An exception rule (`!`) from the ICANN section ends at this node
+pub(crate) const ICANN_EXCEPTION: u8 = 1 << 1;
+/// A normal rule from the PRIVATE section ends at this node
+pub(crate) const PRIVATE_RULE: u8 = 1 << 2;
+/// An exception rule (`!`) from the PRIVATE section ends at this node
+pub(crate) const PRIVATE_EXCEPTION: u8 = 1 << 3;
+/// The node has a `*` child, so the search has to try it as well
+pub(crate) const HAS_WILDCARD: u8 = 1 << 4;
+
+/// Build the tree with a map first, then flatten it into a [FlatTrie]
+#[derive(Debug, Default)]
+pub(crate) struct TLDTrieBuilder {
+  // 节点
+  node: HashMap<String, TLDTrieBuilder>,
+  // 在这里结束的规则
+  flags: u8,
+}
+
+impl TLDTrieBuilder {
+  /// Insert a rule, `*` is kept as a label and `!` marks the last node as an exception,
+  /// `private` is the section of the rule
+  #[inline]
+  pub(crate) fn insert(&mut self, rule: &str, private: bool) {
+    let (rule, is_exception) = match rule.strip_prefix('!') {
+      Some(rule) => (rule, true),
+      None => (rule, false),
+    };
+    let mut current_node = self;
+    for key in rule.rsplit('.') {
+      // 获取下一个节点，没有就插入默认节点
+      current_node = current_node.node.entry(key.to_string()).or_default();
+    }
+    current_node.flags |= match (is_exception, private) {
+      (false, false) => ICANN_RULE,
+      (true, false) => ICANN_EXCEPTION,
+      (false, true) => PRIVATE_RULE,
+      (true, true) => PRIVATE_EXCEPTION,
+    };
+  }
+  /// Flatten the tree breadth first, so the children of every node are contiguous
+  pub(crate) fn flatten(self) -> FlatTrie {
+    let mut tree = FlatTrie {
+      nodes: vec![FlatNode {
+        flags: self.flags,
+        ..Default::default()
+      }],
+      hashes: vec![0],
+      labels: String::new(),
+    };
+    let mut interned: HashMap<String, (u32, u32)> = HashMap::new();
+    let mut queue = VecDeque::new();
+    queue.push_back((0, self.node));
+    while let Some((parent, children)) = queue.pop_front() {
+      let children_start = tree.nodes.len() as u32;
+      let mut children: Vec<(u64, String, TLDTrieBuilder)> = children
+        .into_iter()
+        .map(|(label, child)| (label_hash(&label), label, child))
+        .collect();
+      children.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+      if children.iter().any(|(_, label, _)| label == "*") {
+        tree.nodes[parent].flags |= HAS_WILDCARD;
+      }
+      for (hash, label, child) in children {
+        let (label_start, label_end) = *interned.entry(label).or_insert_with_key(|label| {
+          let start = tree.labels.len() as u32;
+          tree.labels.push_str(label);
+          (start, tree.labels.len() as u32)
+        });
+        queue.push_back((tree.nodes.len(), child.node));
+        tree.hashes.push(hash);
+        tree.nodes.push(FlatNode {
+          label_start,
+          label_end,
+          flags: child.flags,
+          ..Default::default()
+        });
+      }
+      let children_end = tree.nodes.len() as u32;
+      let node = &mut tree.nodes[parent];
+      node.children_start = children_start;
+      node.children_end = children_end;
+    }
+    tree
+  }
+}
+
+/// The flattened tree, node 0 is the root
+#[derive(Debug, Default)]
+pub(crate) struct FlatTrie {
+  pub(crate) nodes: Vec<FlatNode>,
+  pub(crate) hashes: Vec<u64>,
+  pub(crate) labels: String,
+}
+
+/// A node of [FlatTrie], the label and the children are ranges
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct FlatNode {
+  pub(crate) label_start: u32,
+  pub(crate) label_end: u32,
+  pub(crate) children_start: u32,
+  pub(crate) children_end: u32,
+  pub(crate) flags: u8,
+}
@@ -0,0 +1,215 @@
+//! The binary format of [TLDTrieTree], see [TLDTrieTree::to_compiled]
+//!
+//! Everything is little-endian and every section starts at a multiple of 8 bytes:
+//!
+//! | bytes                 | content                                                   |
+//! |-----------------------|-----------------------------------------------------------|
+//! | 0..8                  | magic `TLDTRIE\0`                                         |
+//! | 8..12                 | format version                                            |
+//! | 12..16                | number of nodes                                           |
+//! | 16..20                | length of the labels in bytes                             |
+//! | 20..24                | reserved, 0                                               |
+//! | 24..32                | FNV-1a checksum of everything after the header            |
+//! | nodes                 | label start, label end, children start, children end as u32, flags as u8, 3 bytes padding |
+//! | hashes                | one u64 per node                                          |
+//! | labels                | UTF-8                                                     |
+//!
+//! Loading copies the sections into owned buffers, it saves parsing the list and converting
+//! it with IDNA, not the read. The sections are aligned so a loader could map them later.
+use super::{fnv1a, TLDTrieNode, TLDTrieTree};
+use crate::error::Result;
+use crate::TLDExtractError;
+use std::borrow::Cow;
+
+const MAGIC: &[u8; 8] = b"TLDTRIE\0";
+/// Bump it when the layout or the meaning of the flags changes
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 32;
+const NODE_LEN: usize = 20;
+
+impl TLDTrieTree {
+  /// Encode the tree in a versioned, checksummed binary format, see [TLDTrieTree::from_compiled]
+  pub fn to_compiled(&self) -> Vec<u8> {
+    let nodes_len = align(self.nodes.len() * NODE_LEN);
+    let mut payload = Vec::with_capacity(nodes_len + self.hashes.len() * 8 + self.labels.len());
+    for node in self.nodes.iter() {
+      for value in [
+        node.label_start,
+        node.label_end,
+        node.children_start,
+        node.children_end,
+      ] {
+        payload.extend_from_slice(&value.to_le_bytes());
+      }
+      payload.extend_from_slice(&[node.flags, 0, 0, 0]);
+    }
+    payload.resize(nodes_len, 0);
+    for hash in self.hashes.iter() {
+      payload.extend_from_slice(&hash.to_le_bytes());
+    }
+    payload.extend_from_slice(self.labels.as_bytes());
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(self.nodes.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&(self.labels.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&fnv1a(&payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    bytes
+  }
+  /// Decode a tree written by [TLDTrieTree::to_compiled].
+  ///
+  /// The version, the checksum and every offset are checked, a bad file is a
+  /// [TLDExtractError::BadCompiled] and never a panic at lookup time.
+  // as_chunks 要 Rust 1.88，这里不用它
+  #[allow(clippy::chunks_exact_to_as_chunks)]
+  pub fn from_compiled(bytes: &[u8]) -> Result<Self> {
+    let bad = |reason: &str| TLDExtractError::BadCompiled(reason.to_string());
+    let header = bytes
+      .get(..HEADER_LEN)
+      .ok_or_else(|| bad("truncated header"))?;
+    if &header[..8] != MAGIC {
+      return Err(bad("bad magic"));
+    }
+    let version = u32_at(header, 8);
+    if version != VERSION {
+      return Err(bad(&format!("version {version}, expected {VERSION}")));
+    }
+    let node_count = u32_at(header, 12) as usize;
+    let labels_len = u32_at(header, 16) as usize;
+    let payload = &bytes[HEADER_LEN..];
+    if fnv1a(payload) != u64::from_le_bytes(header[24..32].try_into().unwrap_or_default()) {
+      return Err(bad("checksum mismatch"));
+    }
+    let nodes_len = align(node_count * NODE_LEN);
+    let hashes_end = nodes_len + node_count * 8;
+    if node_count == 0 || payload.len() != hashes_end + labels_len {
+      return Err(bad("bad length"));
+    }
+    let labels =
+      std::str::from_utf8(&payload[hashes_end..]).map_err(|_| bad("labels are not UTF-8"))?;
+    let mut nodes = Vec::with_capacity(node_count);
+    for chunk in payload[..node_count * NODE_LEN].chunks_exact(NODE_LEN) {
+      let node = TLDTrieNode {
+        label_start: u32_at(chunk, 0),
+        label_end: u32_at(chunk, 4),
+        children_start: u32_at(chunk, 8),
+        children_end: u32_at(chunk, 12),
+        flags: chunk[16],
+      };
+      // 查找的时候直接用这些位置切片，加载的时候先检查
+      let label = node.label_start as usize..node.label_end as usize;
+      let children = node.children_start as usize..node.children_end as usize;
+      if labels.get(label).is_none() || children.start > children.end || children.end > node_count {
+        return Err(bad("node out of range"));
+      }
+      nodes.push(node);
+    }
+    let hashes = payload[nodes_len..hashes_end]
+      .chunks_exact(8)
+      .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap_or_default()))
+      .collect();
+    Ok(TLDTrieTree {
+      nodes: Cow::Owned(nodes),
+      hashes: Cow::Owned(hashes),
+      labels: Cow::Owned(labels.to_string()),
+    })
+  }
+}
+
+/// Round up to a multiple of 8
+fn align(len: usize) -> usize {
+  len.div_ceil(8) * 8
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+  u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn compiled() -> Vec<u8> {
+    TLDTrieTree::snapshot().to_compiled()
+  }
+
+  /// Write the checksum again after editing the payload, to get past the checksum check
+  fn reseal(mut bytes: Vec<u8>) -> Vec<u8> {
+    let checksum = fnv1a(&bytes[HEADER_LEN..]);
+    bytes[24..32].copy_from_slice(&checksum.to_le_bytes());
+    bytes
+  }
+
+  fn reason(bytes: &[u8]) -> String {
+    match TLDTrieTree::from_compiled(bytes) {
+      Err(TLDExtractError::BadCompiled(reason)) => reason,
+      other => panic!("{other:?}"),
+    }
+  }
+
+  #[test]
+  fn round_trip() {
+    let tree = TLDTrieTree::from_compiled(&compiled()).unwrap();
+    for host in [
+      "www.example.co.uk",
+      "foo.user.github.io",
+      "www.city.kobe.jp",
+      "a.b.c",
+    ] {
+      assert_eq!(
+        tree.search(host),
+        TLDTrieTree::snapshot().search(host),
+        "{host}"
+      );
+    }
+    assert_eq!(tree.to_compiled(), compiled());
+  }
+
+  #[test]
+  fn bad_header() {
+    let mut bytes = compiled();
+    bytes[0] = b'X';
+    assert_eq!(reason(&bytes), "bad magic");
+    let mut bytes = compiled();
+    bytes[8..12].copy_from_slice(&2u32.to_le_bytes());
+    assert_eq!(reason(&bytes), "version 2, expected 1");
+    assert_eq!(reason(&compiled()[..HEADER_LEN - 1]), "truncated header");
+    assert_eq!(reason(&[]), "truncated header");
+  }
+
+  #[test]
+  fn bad_payload() {
+    let bytes = compiled();
+    // 截断之后校验和对不上
+    assert_eq!(reason(&bytes[..bytes.len() - 1]), "checksum mismatch");
+    let mut flipped = bytes.clone();
+    flipped[HEADER_LEN + 3] ^= 1;
+    assert_eq!(reason(&flipped), "checksum mismatch");
+    // 校验和正确，但是长度和头部不一致
+    let truncated = reseal(bytes[..bytes.len() - 1].to_vec());
+    assert_eq!(reason(&truncated), "bad length");
+    let mut empty = reseal(bytes[..HEADER_LEN].to_vec());
+    empty[12..16].copy_from_slice(&0u32.to_le_bytes());
+    assert_eq!(reason(&reseal(empty)), "bad length");
+  }
+
+  #[test]
+  fn nodes_out_of_range() {
+    let bytes = compiled();
+    let node_count = u32_at(&bytes, 12);
+    let labels_len = u32_at(&bytes, 16);
+    // 根节点的子节点超出节点数量，标签超出 labels，子节点范围倒过来
+    for (offset, value) in [
+      (12, node_count + 1),
+      (4, labels_len + 1),
+      (8, u32_at(&bytes, HEADER_LEN + 12) + 1),
+    ] {
+      let mut bad = bytes.clone();
+      let at = HEADER_LEN + offset;
+      bad[at..at + 4].copy_from_slice(&value.to_le_bytes());
+      assert_eq!(reason(&reseal(bad)), "node out of range", "{offset}");
+    }
+  }
+}
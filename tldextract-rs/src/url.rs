@@ -0,0 +1,182 @@
+use crate::error::Result;
+use crate::TLDExtractError;
+
+/// The pieces of a URL around the host, borrowed from the input
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct UrlParts<'a> {
+  pub(crate) scheme: Option<&'a str>,
+  pub(crate) userinfo: Option<&'a str>,
+  pub(crate) host: &'a str,
+  pub(crate) port: Option<u16>,
+  pub(crate) path: Option<&'a str>,
+  pub(crate) query: Option<&'a str>,
+  pub(crate) fragment: Option<&'a str>,
+}
+
+/// Split a URL (or a bare host) into its parts.
+///
+/// This is deliberately lenient, like the netloc handling of Python tldextract:
+/// the scheme is optional, `//host` is accepted, and everything after the authority
+/// is kept as-is without percent-decoding.
+pub(crate) fn parse(target: &str) -> Result<UrlParts<'_>> {
+  let mut parts = UrlParts::default();
+  let mut rest = target.trim_matches(|ch: char| ch.is_whitespace() || ch.is_control());
+  // 协议部分，例如 https://
+  if let Some(index) = rest.find("://") {
+    let scheme = &rest[..index];
+    if is_scheme(scheme) {
+      parts.scheme = Some(scheme);
+      rest = &rest[index + 3..];
+    }
+  } else if let Some(r) = rest.strip_prefix("//") {
+    rest = r;
+  }
+  // 片段和查询参数
+  if let Some((r, fragment)) = rest.split_once('#') {
+    parts.fragment = Some(fragment);
+    rest = r;
+  }
+  if let Some((r, query)) = rest.split_once('?') {
+    parts.query = Some(query);
+    rest = r;
+  }
+  let mut authority = rest;
+  if let Some(index) = rest.find(['/', '\\']) {
+    authority = &rest[..index];
+    parts.path = Some(&rest[index..]);
+  }
+  // 用户信息，密码中可能也有@，所以从右边找
+  if let Some((userinfo, host)) = authority.rsplit_once('@') {
+    parts.userinfo = Some(userinfo);
+    authority = host;
+  }
+  let (host, port) = split_port(authority);
+  if let Some(port) = port {
+    if !port.is_empty() {
+      match port.parse::<u16>() {
+        Ok(p) => parts.port = Some(p),
+        Err(_) => {
+          return Err(TLDExtractError::InvalidPort(port.to_string()));
+        }
+      }
+    }
+  }
+  parts.host = host;
+  Ok(parts)
+}
+
+/// `scheme = ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )`
+fn is_scheme(scheme: &str) -> bool {
+  let mut chars = scheme.chars();
+  match chars.next() {
+    Some(first) if first.is_ascii_alphabetic() => {
+      chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+    }
+    _ => false,
+  }
+}
+
+/// Split `host:port`, keeping the brackets of an IPv6 literal on the host
+fn split_port(authority: &str) -> (&str, Option<&str>) {
+  if authority.starts_with('[') {
+    if let Some(index) = authority.find(']') {
+      let (host, rest) = authority.split_at(index + 1);
+      return (host, rest.strip_prefix(':'));
+    }
+    return (authority, None);
+  }
+  // 没有中括号的IPv6，不拆分端口
+  if authority.matches(':').count() > 1 {
+    return (authority, None);
+  }
+  // 端口不是数字的话由调用方报 InvalidPort
+  match authority.rsplit_once(':') {
+    Some((host, port)) => (host, Some(port)),
+    None => (authority, None),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn full_url() {
+    let parts = parse("https://user:pw@www.example.co.uk:8443/a?b#c").unwrap();
+    assert_eq!(
+      parts,
+      UrlParts {
+        scheme: Some("https"),
+        userinfo: Some("user:pw"),
+        host: "www.example.co.uk",
+        port: Some(8443),
+        path: Some("/a"),
+        query: Some("b"),
+        fragment: Some("c"),
+      }
+    );
+  }
+
+  #[test]
+  fn userinfo_with_at() {
+    let parts = parse("http://me@example.org:p@ss@example.com/").unwrap();
+    assert_eq!(parts.userinfo, Some("me@example.org:p@ss"));
+    assert_eq!(parts.host, "example.com");
+  }
+
+  #[test]
+  fn bracketed_ipv6() {
+    let parts = parse("http://[2001:db8::1]:8080/index.html").unwrap();
+    assert_eq!(parts.host, "[2001:db8::1]");
+    assert_eq!(parts.port, Some(8080));
+    assert_eq!(parts.path, Some("/index.html"));
+    let parts = parse("[::1]").unwrap();
+    assert_eq!((parts.host, parts.port), ("[::1]", None));
+    // 没有中括号的时候不拆端口
+    assert_eq!(parse("::1").unwrap().host, "::1");
+  }
+
+  #[test]
+  fn scheme_relative() {
+    let parts = parse("//example.com/a").unwrap();
+    assert_eq!(parts.scheme, None);
+    assert_eq!(parts.host, "example.com");
+    assert_eq!(parts.path, Some("/a"));
+    assert_eq!(parse("example.com").unwrap().host, "example.com");
+  }
+
+  #[test]
+  fn query_and_fragment_with_at_and_slash() {
+    let parts = parse("http://example.com?next=/login@evil.com#/a@b").unwrap();
+    assert_eq!(parts.host, "example.com");
+    assert_eq!(parts.userinfo, None);
+    assert_eq!(parts.path, None);
+    assert_eq!(parts.query, Some("next=/login@evil.com"));
+    assert_eq!(parts.fragment, Some("/a@b"));
+  }
+
+  #[test]
+  fn backslash_path() {
+    let parts = parse("http://example.com\\a\\b").unwrap();
+    assert_eq!(parts.host, "example.com");
+    assert_eq!(parts.path, Some("\\a\\b"));
+  }
+
+  #[test]
+  fn ports() {
+    let parts = parse("http://example.com:/").unwrap();
+    assert_eq!((parts.host, parts.port), ("example.com", None));
+    assert!(matches!(
+      parse("http://example.com:abc/"),
+      Err(TLDExtractError::InvalidPort(port)) if port == "abc"
+    ));
+    assert!(matches!(
+      parse("http://example.com:65536/"),
+      Err(TLDExtractError::InvalidPort(port)) if port == "65536"
+    ));
+    assert!(matches!(
+      parse("http://[::1]:x/"),
+      Err(TLDExtractError::InvalidPort(port)) if port == "x"
+    ));
+  }
+}
@@ -0,0 +1,120 @@
+use idna::uts46::{AsciiDenyList, DnsLength, Hyphens, Uts46};
+use idna::Errors;
+use std::borrow::Cow;
+
+/// Options of the IDNA ([UTS #46](https://www.unicode.org/reports/tr46/)) processing
+///
+/// The defaults are the ones of the WHATWG URL standard, which browsers follow.
+/// _CheckBidi_ and _CheckJoiners_ are always on, the `idna` crate cannot turn them off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdnaOptions {
+  /// _Transitional_Processing_: map "ß" to "ss" and "ς" to "σ", and drop ZWJ and ZWNJ,
+  /// like IDNA2003 did
+  pub transitional: bool,
+  /// _UseSTD3ASCIIRules_: only letters, digits and "-" in ASCII, this also rejects "_"
+  pub use_std3_ascii_rules: bool,
+  /// _CheckHyphens_: no "-" at the start or the end of a label, nor at positions 3 and 4
+  pub check_hyphens: bool,
+  /// _VerifyDnsLength_: labels of 1 to 63 bytes and names up to 253 bytes
+  pub verify_dns_length: bool,
+}
+
+impl Default for IdnaOptions {
+  fn default() -> Self {
+    IdnaOptions::new()
+  }
+}
+
+impl IdnaOptions {
+  /// The options of the WHATWG URL standard, everything off
+  #[inline]
+  pub const fn new() -> Self {
+    IdnaOptions {
+      transitional: false,
+      use_std3_ascii_rules: false,
+      check_hyphens: false,
+      verify_dns_length: false,
+    }
+  }
+  /// set transitional
+  #[inline]
+  pub const fn transitional(mut self, transitional: bool) -> Self {
+    self.transitional = transitional;
+    self
+  }
+  /// set use_std3_ascii_rules
+  #[inline]
+  pub const fn use_std3_ascii_rules(mut self, use_std3_ascii_rules: bool) -> Self {
+    self.use_std3_ascii_rules = use_std3_ascii_rules;
+    self
+  }
+  /// set check_hyphens
+  #[inline]
+  pub const fn check_hyphens(mut self, check_hyphens: bool) -> Self {
+    self.check_hyphens = check_hyphens;
+    self
+  }
+  /// set verify_dns_length
+  #[inline]
+  pub const fn verify_dns_length(mut self, verify_dns_length: bool) -> Self {
+    self.verify_dns_length = verify_dns_length;
+    self
+  }
+  /// UTS #46 ToASCII, borrows the input when it is already normalized
+  pub(crate) fn domain_to_ascii<'a>(&self, domain: &'a str) -> Result<Cow<'a, str>, Errors> {
+    let (deny_list, hyphens) = self.flags();
+    let dns_length = if self.verify_dns_length {
+      // 末尾的点之后再处理
+      DnsLength::VerifyAllowRootDot
+    } else {
+      DnsLength::Ignore
+    };
+    let uts46 = Uts46::new();
+    match self.map_transitional(domain) {
+      Cow::Borrowed(d) => uts46.to_ascii(d.as_bytes(), deny_list, hyphens, dns_length),
+      Cow::Owned(d) => uts46
+        .to_ascii(d.as_bytes(), deny_list, hyphens, dns_length)
+        .map(|ascii| Cow::Owned(ascii.into_owned())),
+    }
+  }
+  /// UTS #46 ToUnicode, `None` when it fails
+  pub(crate) fn domain_to_unicode(&self, domain: &str) -> Option<String> {
+    let (deny_list, hyphens) = self.flags();
+    let mapped = self.map_transitional(domain);
+    let (unicode, err) = Uts46::new().to_unicode(mapped.as_bytes(), deny_list, hyphens);
+    match err {
+      Ok(_) if !unicode.is_empty() => Some(unicode.into_owned()),
+      _ => None,
+    }
+  }
+  fn flags(&self) -> (AsciiDenyList, Hyphens) {
+    let deny_list = if self.use_std3_ascii_rules {
+      AsciiDenyList::STD3
+    } else {
+      AsciiDenyList::EMPTY
+    };
+    let hyphens = if self.check_hyphens {
+      Hyphens::Check
+    } else {
+      Hyphens::Allow
+    };
+    (deny_list, hyphens)
+  }
+  /// `idna` 1.x only does non-transitional processing, the deviations are mapped beforehand
+  fn map_transitional<'a>(&self, domain: &'a str) -> Cow<'a, str> {
+    let is_deviation = |ch: char| matches!(ch, 'ß' | 'ẞ' | 'ς' | '\u{200C}' | '\u{200D}');
+    if !self.transitional || !domain.contains(is_deviation) {
+      return Cow::Borrowed(domain);
+    }
+    let mut mapped = String::with_capacity(domain.len());
+    for ch in domain.chars() {
+      match ch {
+        'ß' | 'ẞ' => mapped.push_str("ss"),
+        'ς' => mapped.push('σ'),
+        '\u{200C}' | '\u{200D}' => {}
+        ch => mapped.push(ch),
+      }
+    }
+    Cow::Owned(mapped)
+  }
+}
@@ -0,0 +1,176 @@
+use crate::error::Result;
+use crate::idna2008;
+use crate::{IdnaErrorKind, TLDExtractError, Validation};
+
+/// RFC 1035 2.3.4
+const MAX_LABEL_LEN: usize = 63;
+/// 255 bytes on the wire, minus the length byte of the first label and the root label
+const MAX_NAME_LEN: usize = 253;
+
+/// Check a normalized (lowercase ASCII) host against the validation policy
+pub(crate) fn validate(host: &str, validation: Validation) -> Result<()> {
+  if validation == Validation::Lenient {
+    return Ok(());
+  }
+  if host.len() > MAX_NAME_LEN {
+    return Err(TLDExtractError::NameTooLong { len: host.len() });
+  }
+  let mut offset = 0;
+  for label in host.split('.') {
+    validate_label(label, offset, validation)?;
+    offset += label.len() + 1;
+  }
+  Ok(())
+}
+
+fn validate_label(label: &str, offset: usize, validation: Validation) -> Result<()> {
+  if label.is_empty() {
+    return Err(TLDExtractError::EmptyLabel { offset });
+  }
+  if label.len() > MAX_LABEL_LEN {
+    return Err(TLDExtractError::LabelTooLong {
+      offset,
+      len: label.len(),
+    });
+  }
+  if label.starts_with('-') {
+    return Err(TLDExtractError::Hyphen { offset });
+  }
+  if label.ends_with('-') {
+    return Err(TLDExtractError::Hyphen {
+      offset: offset + label.len() - 1,
+    });
+  }
+  // R-LDH 标签，第3、4个字符是 "--"，只能是 xn-- 开头的 A-label
+  if validation == Validation::Idna2008 && label.get(2..4) == Some("--") {
+    if !label.starts_with("xn--") {
+      return Err(TLDExtractError::Hyphen { offset: offset + 2 });
+    }
+    validate_a_label(label, offset)?;
+  }
+  Ok(())
+}
+
+/// An A-label must decode to a U-label that encodes back to the same A-label (RFC 5891 5.4),
+/// and the code points of the U-label must be allowed by RFC 5892
+fn validate_a_label(label: &str, offset: usize) -> Result<()> {
+  let err = || TLDExtractError::Idna {
+    offset,
+    kind: IdnaErrorKind::Punycode,
+  };
+  let decoded = idna::punycode::decode_to_string(&label[4..]).ok_or_else(err)?;
+  // 全是 ASCII 的话不应该用 Punycode 编码
+  if decoded.is_ascii() {
+    return Err(err());
+  }
+  match idna::domain_to_ascii(&decoded) {
+    Ok(ascii) if ascii == label => {}
+    _ => return Err(err()),
+  }
+  idna2008::check_label(&decoded).map_err(|kind| TLDExtractError::Idna { offset, kind })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn err(host: &str, validation: Validation) -> String {
+    format!("{:?}", validate(host, validation).unwrap_err())
+  }
+
+  #[test]
+  fn lenient() {
+    for host in ["a..b.com", "a-.com", &"a".repeat(300)] {
+      assert!(validate(host, Validation::Lenient).is_ok());
+    }
+  }
+
+  #[test]
+  fn label_and_name_length() {
+    let label = "a".repeat(63);
+    assert!(validate(&format!("{label}.com"), Validation::Rfc1035).is_ok());
+    assert_eq!(
+      err(&format!("www.{label}a.com"), Validation::Rfc1035),
+      "LabelTooLong { offset: 4, len: 64 }"
+    );
+    // 253 字节可以，254 不行
+    let name = [label.as_str(), &label, &label, &"a".repeat(61)].join(".");
+    assert_eq!(name.len(), 253);
+    assert!(validate(&name, Validation::Rfc1035).is_ok());
+    assert_eq!(
+      err(&format!("a{name}"), Validation::Rfc1035),
+      "NameTooLong { len: 254 }"
+    );
+    assert_eq!(
+      err("a..com", Validation::Rfc1035),
+      "EmptyLabel { offset: 2 }"
+    );
+  }
+
+  #[test]
+  fn hyphens() {
+    assert_eq!(
+      err("www.-a.com", Validation::Rfc1035),
+      "Hyphen { offset: 4 }"
+    );
+    assert_eq!(
+      err("www.a-.com", Validation::Rfc1035),
+      "Hyphen { offset: 5 }"
+    );
+    assert!(validate("a-b.com", Validation::Rfc1035).is_ok());
+  }
+
+  #[test]
+  fn r_ldh_labels() {
+    // RFC 1035 不管第3、4个字符
+    assert!(validate("ab--c.com", Validation::Rfc1035).is_ok());
+    assert_eq!(
+      err("www.ab--c.com", Validation::Idna2008),
+      "Hyphen { offset: 6 }"
+    );
+    assert!(validate("www.xn--mnchen-3ya.de", Validation::Idna2008).is_ok());
+    // UTS #46 允许 "☃"，RFC 5892 不允许
+    assert!(idna::domain_to_ascii("☃.com").is_ok());
+    assert_eq!(
+      err("www.xn--n3h.com", Validation::Idna2008),
+      "Idna { offset: 4, kind: DisallowedChar('☃') }"
+    );
+  }
+
+  #[test]
+  fn bad_a_labels() {
+    // 全是 ASCII 的 Punycode 以 "-" 结尾
+    assert_eq!(
+      err("www.xn--abc-.de", Validation::Idna2008),
+      "Hyphen { offset: 11 }"
+    );
+    for label in [
+      // 不是合法的 Punycode
+      "xn--a",
+      // 大写的 A-label 不是规范形式，"MÜnchen"
+      "xn--Mnchen-3ya",
+      // 解码之后 UTS #46 会映射成别的字符
+      "xn--mnchen-3ya9a",
+    ] {
+      let host = format!("www.{label}.de");
+      assert_eq!(
+        err(&host, Validation::Idna2008),
+        "Idna { offset: 4, kind: Punycode }",
+        "{label}"
+      );
+    }
+  }
+
+  #[test]
+  fn idna2008_context() {
+    // "l·l" 可以，其他字母之间的 MIDDLE DOT 不行
+    let ok = idna::domain_to_ascii("col\u{b7}legi.cat").unwrap();
+    assert!(validate(&ok, Validation::Idna2008).is_ok());
+    let bad = idna::domain_to_ascii("www.a\u{b7}b.cat").unwrap();
+    assert!(validate(&bad, Validation::Rfc1035).is_ok());
+    assert_eq!(
+      err(&bad, Validation::Idna2008),
+      "Idna { offset: 4, kind: Context('·') }"
+    );
+  }
+}
@@ -0,0 +1,199 @@
+//! Fetch a remote list through the on-disk cache, against a local HTTP stand-in server.
+#![cfg(feature = "reqwest")]
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tldextract_rs::{Source, SuffixList, TLDTrieTree};
+
+const LIST: &str = "example\nfoo.example\n";
+const ETAG: &str = "\"v1\"";
+const LAST_MODIFIED: &str = "Tue, 15 Oct 2024 07:28:00 GMT";
+
+/// What the stand-in server answers, and the conditional headers it received
+#[derive(Default)]
+struct Server {
+  fail: bool,
+  /// Answer 200 with this body instead of [LIST], whatever the conditional headers
+  body: Option<String>,
+  if_none_match: Option<String>,
+  if_modified_since: Option<String>,
+  requests: usize,
+}
+
+/// Serve [LIST] with an `ETag` and a `Last-Modified`, 304 when the `ETag` matches
+fn serve(state: Arc<Mutex<Server>>) -> String {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let url = format!("http://{}/list.dat", listener.local_addr().unwrap());
+  std::thread::spawn(move || {
+    for stream in listener.incoming() {
+      let mut stream = stream.unwrap();
+      let mut reader = BufReader::new(stream.try_clone().unwrap());
+      let mut headers = Vec::new();
+      loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap() == 0 || line.trim().is_empty() {
+          break;
+        }
+        headers.push(line.trim().to_string());
+      }
+      let header = |name: &str| {
+        headers.iter().find_map(|h| {
+          let (key, value) = h.split_once(": ")?;
+          key.eq_ignore_ascii_case(name).then(|| value.to_string())
+        })
+      };
+      let mut state = state.lock().unwrap();
+      state.requests += 1;
+      state.if_none_match = header("if-none-match");
+      state.if_modified_since = header("if-modified-since");
+      let response = if state.fail {
+        "HTTP/1.1 500 Internal Server Error\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+          .to_string()
+      } else if let Some(body) = &state.body {
+        format!(
+          "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+          body.len()
+        )
+      } else if state.if_none_match.as_deref() == Some(ETAG) {
+        "HTTP/1.1 304 Not Modified\r\nconnection: close\r\n\r\n".to_string()
+      } else {
+        format!(
+          "HTTP/1.1 200 OK\r\netag: {ETAG}\r\nlast-modified: {LAST_MODIFIED}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{LIST}",
+          LIST.len()
+        )
+      };
+      stream.write_all(response.as_bytes()).unwrap();
+    }
+  });
+  url
+}
+
+fn cache_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("tldextract-cache-{name}-{}", std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  dir
+}
+
+fn build(url: &str, cache_dir: Option<PathBuf>) -> tldextract_rs::Result<TLDTrieTree> {
+  let source = Source::Remote(Some(url.parse().unwrap()));
+  SuffixList::new(source, false, None)
+    .cache_dir(cache_dir)
+    .build()
+}
+
+#[test]
+fn revalidate_and_fall_back_to_cache() {
+  let state = Arc::new(Mutex::new(Server::default()));
+  let url = serve(state.clone());
+  let dir = cache_dir("revalidate");
+  // 第一次请求，没有缓存
+  let tree = build(&url, Some(dir.clone())).unwrap();
+  assert_eq!(tree.search("a.foo.example").len, 2);
+  assert_eq!(state.lock().unwrap().if_none_match, None);
+  assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+  // 第二次带上 ETag，服务器返回 304，使用缓存
+  let tree = build(&url, Some(dir.clone())).unwrap();
+  assert_eq!(tree.search("a.foo.example").len, 2);
+  {
+    let state = state.lock().unwrap();
+    assert_eq!(state.requests, 2);
+    assert_eq!(state.if_none_match.as_deref(), Some(ETAG));
+    assert_eq!(state.if_modified_since.as_deref(), Some(LAST_MODIFIED));
+  }
+  // 服务器出错的时候使用缓存
+  state.lock().unwrap().fail = true;
+  let tree = build(&url, Some(dir.clone())).unwrap();
+  assert_eq!(tree.search("a.foo.example").len, 2);
+  // 没有缓存的话就是错误
+  assert!(build(&url, None).is_err());
+  assert!(build(&url, Some(cache_dir("empty"))).is_err());
+  std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn chain_records_the_source_that_supplied() {
+  let state = Arc::new(Mutex::new(Server::default()));
+  let url = serve(state.clone());
+  let dead = {
+    // 绑定之后马上关闭，连接会被拒绝
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}/list.dat", listener.local_addr().unwrap())
+  };
+  let dir = cache_dir("chain");
+  let chain = |sources: Vec<Source>| {
+    let mut suffix =
+      SuffixList::new(Source::Chain(sources), false, None).cache_dir(Some(dir.clone()));
+    suffix
+      .build()
+      .map(|tree| (tree, suffix.provenance.unwrap()))
+  };
+  let remote = |url: &str| Source::Remote(Some(url.parse().unwrap()));
+  let cache = |url: &str| Source::Cache(Some(url.parse().unwrap()));
+  // 死掉的镜像，没有缓存，使用快照
+  let (tree, provenance) = chain(vec![remote(&dead), cache(&url), Source::Snapshot]).unwrap();
+  assert!(matches!(provenance.source, Source::Snapshot));
+  assert_eq!(tree.search("example.co.uk").len, 2);
+  // 第二个镜像可用
+  let (tree, provenance) = chain(vec![remote(&dead), remote(&url), Source::Snapshot]).unwrap();
+  assert_eq!(provenance.source.to_string(), url);
+  assert!(!provenance.from_cache);
+  assert!(provenance.updated.is_some());
+  assert_eq!(tree.search("a.foo.example").len, 2);
+  // 服务器出错，使用上一次下载的缓存
+  state.lock().unwrap().fail = true;
+  let (_, provenance) = chain(vec![remote(&url), Source::Snapshot]).unwrap();
+  assert_eq!(provenance.to_string(), format!("{url} (cached)"));
+  assert!(provenance.from_cache);
+  let (tree, provenance) = chain(vec![remote(&dead), cache(&url), Source::Snapshot]).unwrap();
+  assert!(matches!(provenance.source, Source::Cache(Some(_))));
+  assert_eq!(tree.search("a.foo.example").len, 2);
+  // 全部失败的话返回最后一个错误
+  assert!(chain(vec![remote(&dead), Source::Text(String::new())]).is_err());
+  std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn keep_the_cache_when_the_download_has_no_rules() {
+  let state = Arc::new(Mutex::new(Server::default()));
+  let url = serve(state.clone());
+  let dir = cache_dir("no-rules");
+  build(&url, Some(dir.clone())).unwrap();
+  let cached = || {
+    let source = Source::Cache(Some(url.parse().unwrap()));
+    SuffixList::new(source, false, None)
+      .cache_dir(Some(dir.clone()))
+      .build()
+  };
+  let remote = |strict: bool| {
+    let source = Source::Remote(Some(url.parse().unwrap()));
+    SuffixList::new(source, false, None)
+      .cache_dir(Some(dir.clone()))
+      .strict(strict)
+      .build()
+  };
+  // 空的、只有注释的、只有无效规则的、严格解析失败的列表都不覆盖缓存
+  for (body, strict, is_err) in [
+    ("", false, true),
+    ("// nothing here\n", false, false),
+    ("exa\u{e000}mple\n", false, false),
+    ("exa\u{e000}mple\nother\n", true, true),
+  ] {
+    state.lock().unwrap().body = Some(body.to_string());
+    assert_eq!(remote(strict).is_err(), is_err, "{body:?}");
+    assert_eq!(cached().unwrap().search("a.foo.example").len, 2, "{body:?}");
+  }
+  // 新的列表解析成功之后才替换缓存
+  state.lock().unwrap().body = Some("other\n".to_string());
+  build(&url, Some(dir.clone())).unwrap();
+  let tree = cached().unwrap();
+  assert_eq!(tree.search("a.other").len, 1);
+  assert_eq!(tree.search("a.foo.example").len, 0);
+  std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn default_and_new_cache_alike() {
+  let new = SuffixList::new(Source::Snapshot, false, None);
+  assert_eq!(SuffixList::default().cache_dir, new.cache_dir);
+}
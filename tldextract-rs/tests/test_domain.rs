@@ -0,0 +1,81 @@
+//! Walk up every rule of the snapshot with [Domain::parent] and check each ancestor
+//! against what [TLDExtract] says about the same name.
+use tldextract_rs::{Domain, Source, SuffixList, TLDExtract, UnknownTld};
+
+/// A host a couple of labels under every rule, "*" and "!" filled in
+fn hosts() -> Vec<String> {
+  let mut suffix = SuffixList::new(Source::Snapshot, false, None);
+  suffix.build().unwrap();
+  let mut hosts: Vec<String> = suffix
+    .public_suffixes
+    .iter()
+    .chain(suffix.private_suffixes.iter())
+    .map(|rule| format!("x.y.{}", rule.replace('*', "w").replace('!', "")))
+    .collect();
+  hosts.sort();
+  hosts.push("x.y.example.notatld".to_string());
+  hosts
+}
+
+/// Every ancestor agrees with a fresh extraction of its name
+fn check(extract: &TLDExtract, domain: Domain) {
+  let mut next = Some(domain);
+  while let Some(domain) = next {
+    let name = domain.as_str();
+    let result = extract.extract(name).unwrap();
+    assert_eq!(domain.suffix(), result.suffix.as_deref(), "{name}");
+    assert_eq!(
+      domain.registered_domain(),
+      result.registered_domain.as_deref(),
+      "{name}"
+    );
+    // 没有后缀的时候 Domain 没有子域名
+    if result.suffix.is_some() {
+      assert_eq!(domain.subdomain(), result.subdomain.as_deref(), "{name}");
+    }
+    assert_eq!(domain.is_private(), result.is_private, "{name}");
+    assert_eq!(
+      domain.is_public_suffix(),
+      extract.is_public_suffix(name),
+      "{name}"
+    );
+    assert_eq!(
+      domain.is_registrable(),
+      extract.is_registrable_domain(name),
+      "{name}"
+    );
+    next = domain.parent();
+  }
+}
+
+#[test]
+fn ancestors_agree_with_extract() {
+  for extract in [
+    TLDExtract::snapshot(false),
+    TLDExtract::snapshot(false).unknown_tld(UnknownTld::Default),
+  ] {
+    for host in hosts() {
+      check(&extract, extract.domain(&host).unwrap());
+    }
+  }
+}
+
+#[test]
+fn ancestors_use_the_tree_of_the_extractor() {
+  let suffix = SuffixList::new(Source::Text("com\n*.a.com\n".to_string()), false, None);
+  let extract = TLDExtract::new(suffix, false).unwrap();
+  let domain = extract.domain("x.y.b.a.com").unwrap();
+  assert_eq!(domain.suffix(), Some("b.a.com"));
+  let names: Vec<String> = domain
+    .ancestors_until_registrable()
+    .map(|d| d.to_string())
+    .collect();
+  assert_eq!(names, ["x.y.b.a.com", "y.b.a.com"]);
+  let suffix = domain.parent().unwrap().parent().unwrap();
+  assert!(suffix.is_public_suffix());
+  // "a.com" 在 com 下面可以注册
+  let a = suffix.parent().unwrap();
+  assert!(a.is_registrable());
+  assert_eq!(a.suffix(), Some("com"));
+  check(&extract, domain);
+}
@@ -0,0 +1,285 @@
+//! Extraction through the public API, with the compiled snapshot
+use tldextract_rs::{
+  ExtractResult, IdnaErrorKind, RuleOrigin, SkippedLine, Source, SuffixList, TLDExtract,
+  TLDExtractError, TLDTrieTree, UnknownTld, Validation,
+};
+
+fn extractor() -> TLDExtract {
+  TLDExtract::snapshot(false)
+}
+
+/// Whether `part` is a slice of `input`
+fn borrowed_from(part: &str, input: &str) -> bool {
+  let range = input.as_bytes().as_ptr_range();
+  range.contains(&part.as_ptr()) && part.len() <= input.len()
+}
+
+#[test]
+fn borrowed_when_normalized() {
+  let extract = extractor();
+  let input = "https://mirrors.tuna.tsinghua.edu.cn/a";
+  let extract_ref = extract.extract_ref(input).unwrap();
+  assert_eq!(extract_ref.host(), "mirrors.tuna.tsinghua.edu.cn");
+  assert_eq!(extract_ref.subdomain(), Some("mirrors.tuna"));
+  assert_eq!(extract_ref.domain(), Some("tsinghua"));
+  assert_eq!(extract_ref.suffix(), Some("edu.cn"));
+  assert_eq!(extract_ref.registered_domain(), Some("tsinghua.edu.cn"));
+  for part in [
+    extract_ref.host(),
+    extract_ref.subdomain().unwrap(),
+    extract_ref.suffix().unwrap(),
+  ] {
+    assert!(borrowed_from(part, input), "{part}");
+  }
+  // 末尾的点不影响借用
+  let extract_ref = extract.extract_ref("example.co.uk.").unwrap();
+  assert!(extract_ref.fqdn());
+  assert!(borrowed_from(extract_ref.host(), "example.co.uk."));
+}
+
+#[test]
+fn owned_when_normalization_changes_the_host() {
+  let extract = extractor();
+  for (input, host) in [
+    ("WWW.Example.CO.UK", "www.example.co.uk"),
+    ("www.例子.中国", "www.xn--fsqu00a.xn--fiqs8s"),
+  ] {
+    let extract_ref = extract.extract_ref(input).unwrap();
+    assert_eq!(extract_ref.host(), host);
+    assert!(!borrowed_from(extract_ref.host(), input), "{input}");
+    assert_eq!(extract_ref.subdomain(), Some("www"));
+  }
+}
+
+#[test]
+fn borrowed_and_owned_results_agree() {
+  let extract = extractor();
+  for input in [
+    "www.example.co.uk",
+    "WWW.Example.CO.UK",
+    "foo.user.github.io",
+    "example.notatld",
+    "co.uk",
+    "[::1]",
+    "127.0.0.1",
+  ] {
+    let owned = extract.extract(input).unwrap();
+    let borrowed = ExtractResult::from(extract.extract_ref(input).unwrap());
+    assert_eq!(format!("{owned:?}"), format!("{borrowed:?}"), "{input}");
+  }
+}
+
+#[test]
+fn private_section() {
+  let extract = extractor();
+  for (input, suffix, is_private) in [
+    ("foo.blogspot.com", "blogspot.com", true),
+    ("user.github.io", "github.io", true),
+    ("www.example.co.uk", "co.uk", false),
+    ("github.io", "github.io", true),
+    ("io", "io", false),
+  ] {
+    let result = extract.extract(input).unwrap();
+    assert_eq!(result.suffix.as_deref(), Some(suffix), "{input}");
+    assert_eq!(result.is_private, is_private, "{input}");
+    assert_eq!(extract.extract_ref(input).unwrap().is_private(), is_private);
+  }
+  assert!(!extract.extract("127.0.0.1").unwrap().is_private);
+  // 不使用私有域名的时候，不会匹配到私有规则
+  let icann_only = TLDExtract::new(SuffixList::new(Source::Snapshot, true, None), false).unwrap();
+  let result = icann_only.extract("user.github.io").unwrap();
+  assert_eq!(result.suffix.as_deref(), Some("io"));
+  assert!(!result.is_private);
+}
+
+#[test]
+fn private_and_icann_split() {
+  let extract = extractor();
+  let split = extract.extract_split("foo.user.github.io").unwrap();
+  assert_eq!(split.private.subdomain.as_deref(), Some("foo"));
+  assert_eq!(split.private.domain.as_deref(), Some("user"));
+  assert_eq!(split.private.suffix.as_deref(), Some("github.io"));
+  assert!(split.private.is_private);
+  assert_eq!(split.icann.subdomain.as_deref(), Some("foo.user"));
+  assert_eq!(split.icann.domain.as_deref(), Some("github"));
+  assert_eq!(split.icann.suffix.as_deref(), Some("io"));
+  assert_eq!(split.icann.registered_domain.as_deref(), Some("github.io"));
+  assert!(!split.icann.is_private);
+  // 借用的结果一样
+  let extract_ref = extract.extract_ref("foo.user.github.io").unwrap();
+  let icann = extract_ref.icann();
+  assert_eq!(icann.suffix(), Some("io"));
+  assert_eq!(icann.registered_domain(), Some("github.io"));
+  // 只有 ICANN 规则的时候两种拆分一样
+  let split = extract.extract_split("www.example.co.uk").unwrap();
+  assert_eq!(format!("{:?}", split.private), format!("{:?}", split.icann));
+  // 私有规则建在 ICANN 后缀上面
+  let split = extract.extract_split("a.b.blogspot.co.uk").unwrap();
+  assert_eq!(split.private.suffix.as_deref(), Some("blogspot.co.uk"));
+  assert_eq!(split.icann.suffix.as_deref(), Some("co.uk"));
+  assert_eq!(
+    split.icann.registered_domain.as_deref(),
+    Some("blogspot.co.uk")
+  );
+  let split = extract.extract_split("[::1]").unwrap();
+  assert_eq!(split.icann.ip, split.private.ip);
+}
+
+#[test]
+fn explain_rule_text_and_origin() {
+  let suffix = SuffixList::new(Source::Text("com\n".to_string()), false, None)
+    .extra(Source::Text("// 额外的规则\n*.例子.com\n".to_string()));
+  let extract = TLDExtract::new(suffix, false).unwrap();
+  let explain = extract.explain("a.b.例子.com").unwrap();
+  let rules: Vec<_> = explain
+    .rules
+    .iter()
+    .map(|r| (r.rule.as_str(), r.text.as_str(), r.origin))
+    .collect();
+  assert_eq!(
+    rules,
+    [
+      ("com", "com", Some(RuleOrigin::Main)),
+      ("*.xn--fsqu00a.com", "*.例子.com", Some(RuleOrigin::Extra)),
+    ]
+  );
+  assert_eq!(explain.prevailing.unwrap().text, "*.例子.com");
+  // 从编译好的文件加载，不知道原来的写法
+  let path = std::env::temp_dir().join(format!("tldextract-explain-{}.bin", std::process::id()));
+  std::fs::write(&path, TLDTrieTree::snapshot().to_compiled()).unwrap();
+  let extract = TLDExtract::load_compiled(&path, false).unwrap();
+  std::fs::remove_file(path).unwrap();
+  let rule = extract.explain("foo.个人.hk").unwrap().prevailing.unwrap();
+  assert_eq!((rule.text.as_str(), rule.origin), ("xn--ciqpn.hk", None));
+}
+
+#[test]
+fn structured_errors() {
+  let extract = extractor();
+  let err = |input: &str| format!("{:?}", extract.extract(input).unwrap_err());
+  assert_eq!(
+    err("www.exa!mple.com"),
+    "InvalidChar { offset: 7, ch: '!' }"
+  );
+  assert_eq!(err("-example.com"), "Hyphen { offset: 0 }");
+  assert_eq!(
+    err("www.a\u{e000}.com"),
+    "Idna { offset: 5, kind: DisallowedChar('\\u{e000}') }"
+  );
+  assert_eq!(err("http://example.com:abc/"), "InvalidPort(\"abc\")");
+  assert_eq!(err("1.2.3.256"), "InvalidIp(\"1.2.3.256\")");
+  assert!(matches!(
+    extract.extract("www.xn--a.com"),
+    Err(TLDExtractError::Idna {
+      offset: 4,
+      kind: IdnaErrorKind::Punycode
+    })
+  ));
+  // UTS #46 允许的符号，IDNA2008 不允许
+  assert!(extract.extract("www.☃.com").is_ok());
+  assert!(matches!(
+    extractor()
+      .validation(Validation::Idna2008)
+      .extract("www.☃.com"),
+    Err(TLDExtractError::Idna {
+      offset: 4,
+      kind: IdnaErrorKind::DisallowedChar('☃')
+    })
+  ));
+  // 只有子域名可以有下划线
+  let underscore = extractor().allow_underscore(true);
+  assert!(underscore.extract("_dmarc.example.com").is_ok());
+  assert!(matches!(
+    underscore.extract("a.ex_ample.com"),
+    Err(TLDExtractError::InvalidChar { offset: 4, ch: '_' })
+  ));
+  let strict = extractor().unknown_tld(UnknownTld::Error).reject_dots(true);
+  assert!(matches!(
+    strict.extract("example.notatld"),
+    Err(TLDExtractError::UnknownTld(tld)) if tld == "notatld"
+  ));
+  assert!(matches!(
+    strict.extract(".example.com"),
+    Err(TLDExtractError::EmptyLabel { offset: 0 })
+  ));
+  assert!(matches!(
+    strict.extract("example.com."),
+    Err(TLDExtractError::EmptyLabel { offset: 12 })
+  ));
+  assert!(matches!(
+    extract.domain("[::1]"),
+    Err(TLDExtractError::NotDomain(host)) if host == "[::1]"
+  ));
+  assert!(matches!(
+    SuffixList::new(Source::Text("com\nexa\u{e000}mple.com\n".to_string()), false, None)
+      .strict(true)
+      .build(),
+    Err(TLDExtractError::BadListLine { line: 2, text }) if text == "exa\u{e000}mple.com"
+  ));
+}
+
+#[test]
+fn failed_update_keeps_the_list_and_its_provenance() {
+  let suffix = SuffixList::new(Source::Text("com\n".to_string()), false, None);
+  let extract = TLDExtract::new(suffix, false).unwrap();
+  let missing = SuffixList::new(Source::Local("/no/such/list.dat".into()), false, None);
+  extract.update(Some(missing));
+  assert_eq!(extract.provenance().unwrap().to_string(), "text");
+  assert_eq!(extract.explain("www.example.com").unwrap().source, "text");
+  // 之后的 update 重新构建的还是原来的列表
+  extract.update(None);
+  assert_eq!(extract.provenance().unwrap().to_string(), "text");
+  let result = extract.extract("www.example.com").unwrap();
+  assert_eq!(result.registered_domain.as_deref(), Some("example.com"));
+}
+
+#[test]
+fn explain_after_a_failed_update() {
+  let suffix = SuffixList::new(Source::Text("例子.com\n".to_string()), false, None);
+  let extract = TLDExtract::new(suffix, false).unwrap();
+  let missing = SuffixList::new(Source::Local("/no/such/list.dat".into()), false, None);
+  extract.update(Some(missing));
+  // 还在用的树的规则，写法和来源都还在
+  let rule = extract.explain("a.b.例子.com").unwrap().prevailing.unwrap();
+  assert_eq!(rule.rule, "xn--fsqu00a.com");
+  assert_eq!(
+    (rule.text.as_str(), rule.origin),
+    ("例子.com", Some(RuleOrigin::Main))
+  );
+}
+
+#[test]
+fn bad_list_lines_are_skipped() {
+  let suffix = SuffixList::new(
+    Source::Text("com\nexa\u{e000}mple.com\nnet\n".to_string()),
+    false,
+    None,
+  )
+  .extra(Source::Text("// 额外的规则\nb\u{e000}d.org\n".to_string()));
+  let extract = TLDExtract::new(suffix.clone(), false).unwrap();
+  let result = extract.extract("www.example.net").unwrap();
+  assert_eq!(result.registered_domain.as_deref(), Some("example.net"));
+  let mut suffix = suffix;
+  suffix.build().unwrap();
+  assert_eq!(
+    suffix.skipped_lines,
+    [
+      SkippedLine {
+        origin: RuleOrigin::Main,
+        line: 2,
+        text: "exa\u{e000}mple.com".to_string()
+      },
+      SkippedLine {
+        origin: RuleOrigin::Extra,
+        line: 2,
+        text: "b\u{e000}d.org".to_string()
+      },
+    ]
+  );
+  // 更严格的 IDNA 选项也只是多跳过几行
+  let strict_idna = tldextract_rs::IdnaOptions::new().use_std3_ascii_rules(true);
+  let mut suffix =
+    SuffixList::new(Source::Text("com\nex_ample.com\n".to_string()), false, None).idna(strict_idna);
+  assert!(suffix.build().is_ok());
+  assert_eq!(suffix.skipped_lines.len(), 1);
+}
@@ -0,0 +1,57 @@
+//! Run the official test vectors of the Public Suffix List (`tests/test_psl.txt`)
+//! against both the compiled snapshot and a tree built at runtime.
+use tldextract_rs::{Source, SuffixList, TLDExtract, UnknownTld};
+
+const TEST_PSL: &str = include_str!("test_psl.txt");
+
+/// The PSL algorithm through the public API: the implicit "*" rule for unlisted TLDs,
+/// and no empty labels, like a leading dot. `None` for invalid input
+fn check_public_suffix(extract: &TLDExtract, domain: Option<&str>) -> Option<String> {
+  extract.extract(domain?).ok()?.registered_domain
+}
+
+fn psl(extract: TLDExtract) -> TLDExtract {
+  extract.unknown_tld(UnknownTld::Default).reject_dots(true)
+}
+
+/// `checkPublicSuffix('input', 'expected');` or `checkPublicSuffix(null, null);`
+fn parse_args(line: &str) -> Option<(Option<&str>, Option<&str>)> {
+  let args = line
+    .strip_prefix("checkPublicSuffix(")?
+    .strip_suffix(");")?;
+  let (input, expected) = args.split_once(',')?;
+  Some((parse_arg(input), parse_arg(expected)))
+}
+
+/// A quoted string, `null` otherwise
+fn parse_arg(arg: &str) -> Option<&str> {
+  let arg = arg.trim();
+  arg.strip_prefix('\'').and_then(|a| a.strip_suffix('\''))
+}
+
+fn run(extract: TLDExtract) {
+  let extract = psl(extract);
+  let mut checked = 0;
+  for line in TEST_PSL.lines().map(str::trim) {
+    if line.is_empty() || line.starts_with("//") {
+      continue;
+    }
+    let (input, expected) = parse_args(line).unwrap_or_else(|| panic!("bad line: {line}"));
+    // 期望值也用 ASCII 比较
+    let expected = expected.map(|e| idna::domain_to_ascii(e).unwrap());
+    assert_eq!(check_public_suffix(&extract, input), expected, "{line}");
+    checked += 1;
+  }
+  assert!(checked > 0);
+}
+
+#[test]
+fn snapshot() {
+  run(TLDExtract::snapshot(false));
+}
+
+#[test]
+fn runtime() {
+  let suffix = SuffixList::new(Source::Snapshot, false, None);
+  run(TLDExtract::new(suffix, false).unwrap());
+}